@@ -1,7 +1,22 @@
+use std::env;
 use std::error::Error;
+use std::path::PathBuf;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    // compiling protos using path on build time
-    tonic_build::compile_protos("proto/project.proto")?;
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+
+    // compiling protos using path on build time, keeping the descriptor set for server reflection
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("agora_descriptor.bin"))
+        .extern_path(".google.rpc", "::tonic_types::pb")
+        .compile(
+            &[
+                "proto/project.proto",
+                "proto/agora/project/v1/project.proto",
+                "proto/agora/character/v1/character.proto",
+            ],
+            &["proto"],
+        )?;
+
     Ok(())
 }
@@ -3,6 +3,7 @@ extern crate log;
 #[macro_use]
 extern crate lazy_static;
 
+use agora::correlation;
 use agora::file::event_bus::RabbitMqFileBus;
 use agora::file::event_handler::FileEventHandler;
 use agora::project::application::ProjectApplication;
@@ -15,11 +16,15 @@ use lapin::{Channel, Connection, ConnectionProperties, ExchangeKind};
 use std::env;
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 use surrealdb::engine::remote::ws::{Client, Ws};
 use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
+use tokio::sync::broadcast;
 
 const DEFAULT_APP_ID: &str = "agora";
+const DEFAULT_TRASH_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+const DEFAULT_TRASH_PURGE_INTERVAL_SECS: u64 = 60 * 60;
 
 const ENV_SURREAL_DSN: &str = "SURREAL_DSN";
 const ENV_SURREAL_NS: &str = "SURREAL_NS";
@@ -32,6 +37,8 @@ const ENV_RABBITMQ_DSN: &str = "RABBITMQ_DSN";
 const ENV_EVENT_ISSUER: &str = "EVENT_ISSUER";
 const ENV_ISSUERS_WHITELIST: &str = "ISSUERS_WHITELIST";
 const ENV_APP_ID: &str = "APP_ID";
+const ENV_TRASH_RETENTION_SECS: &str = "TRASH_RETENTION_SECS";
+const ENV_TRASH_PURGE_INTERVAL_SECS: &str = "TRASH_PURGE_INTERVAL_SECS";
 
 lazy_static! {
     static ref APP_ID: String = env::var(ENV_APP_ID).unwrap_or(DEFAULT_APP_ID.to_string());
@@ -118,11 +125,23 @@ lazy_static! {
     static ref ISSUERS_WHITELIST: Vec<String> = env::var(ENV_ISSUERS_WHITELIST)
         .map(|s| s.split(';').map(Into::into).collect())
         .expect("issuers whitelist must be set");
+    static ref TRASH_RETENTION: Duration = env::var(ENV_TRASH_RETENTION_SECS)
+        .map(|secs| secs
+            .parse()
+            .expect("trash retention must be a number of seconds"))
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_TRASH_RETENTION_SECS));
+    static ref TRASH_PURGE_INTERVAL: Duration = env::var(ENV_TRASH_PURGE_INTERVAL_SECS)
+        .map(|secs| secs
+            .parse()
+            .expect("trash purge interval must be a number of seconds"))
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_TRASH_PURGE_INTERVAL_SECS));
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    env_logger::init();
+    correlation::init_logger();
 
     if let Err(err) = dotenv::dotenv() {
         warn!("processing dotenv file {}", err);
@@ -139,11 +158,33 @@ async fn main() -> Result<(), Box<dyn Error>> {
         exchange: &RABBITMQ_FILES_EXCHANGE,
     });
 
+    // changes performed by the agent are not watched by anyone, nor retried under any idempotency key
+    let (changes, _) = broadcast::channel(1);
+
     let project_app = ProjectApplication {
         project_repo: project_repo.clone(),
-        event_bus: file_event_bus,
+        event_bus: file_event_bus.clone(),
+        changes: changes.clone(),
+        idempotency_ttl: Duration::ZERO,
+    };
+
+    let trash_app = ProjectApplication {
+        project_repo: project_repo.clone(),
+        event_bus: file_event_bus.clone(),
+        changes,
+        idempotency_ttl: Duration::ZERO,
     };
 
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(*TRASH_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(err) = trash_app.purge_expired(*TRASH_RETENTION).await {
+                error!("{} purging expired projects from trash", err);
+            }
+        }
+    });
+
     let file_event_handler = FileEventHandler {
         project_app,
         issuers_whitelist: &ISSUERS_WHITELIST,
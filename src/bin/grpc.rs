@@ -3,26 +3,81 @@ extern crate log;
 #[macro_use]
 extern crate lazy_static;
 
+use agora::character::application::CharacterApplication;
+use agora::character::grpc::{CharacterServiceServer, GrpcCharacterServer};
+use agora::character::repository::SurrealCharacterRepository;
+use agora::correlation;
 use agora::file::event_bus::RabbitMqFileBus;
-use agora::project::application::ProjectApplication;
-use agora::project::grpc::{GrpcProjectServer, ProjectServiceServer};
+use agora::grpc::rate_limit::{Quota, RateLimitLayer, RateLimits};
+use agora::grpc::{AuthLayer, RequestIdLayer, FILE_DESCRIPTOR_SET};
+use agora::project::application::{ProjectApplication, ShareApplication};
+use agora::project::grpc::{v1, GrpcProjectServer, ProjectServiceServer};
 use agora::project::repository::SurrealProjectRepository;
+use agora::project::token::JwtTokenService;
+use agora::template::application::TemplateApplication;
+use agora::template::repository::SurrealTemplateRepository;
 use async_once::AsyncOnce;
 use lapin::options::ExchangeDeclareOptions;
 use lapin::types::FieldTable;
 use lapin::{Channel, Connection, ConnectionProperties, ExchangeKind};
 use std::env;
 use std::error::Error;
+use std::fs;
 use std::sync::Arc;
+use std::time::Duration;
 use surrealdb::engine::remote::ws::{Client, Ws};
 use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
+use tokio::sync::broadcast;
+use tonic::codegen::http::{HeaderName, HeaderValue, Method};
 use tonic::transport::Server;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tonic_web::GrpcWebLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
 const DEFAULT_NETW: &str = "127.0.0.1";
 const DEFAULT_PORT: &str = "8000";
 const DEFAULT_UID_HEADER: &str = "X-Uid";
 const DEFAULT_APP_ID: &str = "agora";
+const DEFAULT_WATCH_CAPACITY: usize = 64;
+const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 10;
+const DEFAULT_AUTH_MODE: &str = "jwt";
+const DEFAULT_CORS_ALLOWED_ORIGINS: &str = "*";
+const DEFAULT_CORS_MAX_AGE_SECS: u64 = 1728000;
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_RATE_LIMIT_GLOBAL: &str = "1000/1";
+const DEFAULT_RATE_LIMIT_USER: &str = "20/1";
+
+const CORS_ALLOWED_HEADERS: [&str; 14] = [
+    "keep-alive",
+    "user-agent",
+    "cache-control",
+    "content-type",
+    "content-transfer-encoding",
+    "grpc-status-details-bin",
+    "x-accept-content-transfer-encoding",
+    "x-accept-response-streaming",
+    "x-user-agent",
+    "x-grpc-web",
+    "grpc-timeout",
+    "authorization",
+    "x-request-id",
+    "idempotency-key",
+];
+
+const CORS_EXPOSED_HEADERS: [&str; 5] = [
+    "grpc-status-details-bin",
+    "grpc-status",
+    "grpc-message",
+    "x-request-id",
+    "retry-after",
+];
+const SERVICE_NAMES: [&str; 3] = [
+    "project.ProjectService",
+    "agora.project.v1.ProjectService",
+    "agora.character.v1.CharacterService",
+];
 
 const ENV_SERVICE_PORT: &str = "SERVICE_PORT";
 const ENV_SERVICE_NETW: &str = "SERVICE_NETW";
@@ -37,6 +92,20 @@ const ENV_RABBITMQ_FILES_QUEUE: &str = "RABBITMQ_FILES_QUEUE";
 const ENV_RABBITMQ_DSN: &str = "RABBITMQ_DSN";
 const ENV_EVENT_ISSUER: &str = "EVENT_ISSUER";
 const ENV_APP_ID: &str = "APP_ID";
+const ENV_SHARE_TOKEN_SECRET: &str = "SHARE_TOKEN_SECRET";
+const ENV_WATCH_CAPACITY: &str = "WATCH_CAPACITY";
+const ENV_HEALTH_CHECK_INTERVAL_SECS: &str = "HEALTH_CHECK_INTERVAL_SECS";
+const ENV_AUTH_MODE: &str = "AUTH_MODE";
+const ENV_CORS_ALLOWED_ORIGINS: &str = "CORS_ALLOWED_ORIGINS";
+const ENV_CORS_MAX_AGE_SECS: &str = "CORS_MAX_AGE_SECS";
+const ENV_IDEMPOTENCY_TTL_SECS: &str = "IDEMPOTENCY_TTL_SECS";
+const ENV_RATE_LIMIT_GLOBAL: &str = "RATE_LIMIT_GLOBAL";
+const ENV_RATE_LIMIT_USER: &str = "RATE_LIMIT_USER";
+const ENV_RATE_LIMIT_METHODS: &str = "RATE_LIMIT_METHODS";
+const ENV_JWT_SECRET: &str = "JWT_SECRET";
+const ENV_JWT_PUBLIC_KEY_PATH: &str = "JWT_PUBLIC_KEY_PATH";
+const ENV_JWT_AUDIENCE: &str = "JWT_AUDIENCE";
+const ENV_JWT_ISSUER: &str = "JWT_ISSUER";
 
 lazy_static! {
     static ref APP_ID: String = env::var(ENV_APP_ID).unwrap_or(DEFAULT_APP_ID.to_string());
@@ -127,11 +196,185 @@ lazy_static! {
     static ref RABBITMQ_FILES_QUEUE: String =
         env::var(ENV_RABBITMQ_FILES_QUEUE).expect("rabbitmq files queue must be set");
     static ref EVENT_ISSUER: String = env::var(ENV_EVENT_ISSUER).expect("event issuer must be set");
+    static ref SHARE_TOKEN_SECRET: String =
+        env::var(ENV_SHARE_TOKEN_SECRET).expect("share token secret must be set");
+    static ref WATCH_CAPACITY: usize = env::var(ENV_WATCH_CAPACITY)
+        .map(|capacity| capacity.parse().expect("watch capacity must be a number"))
+        .unwrap_or(DEFAULT_WATCH_CAPACITY);
+    static ref HEALTH_CHECK_INTERVAL: Duration = env::var(ENV_HEALTH_CHECK_INTERVAL_SECS)
+        .map(|secs| secs
+            .parse()
+            .expect("health check interval must be a number of seconds"))
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_HEALTH_CHECK_INTERVAL_SECS));
+    static ref IDEMPOTENCY_TTL: Duration = env::var(ENV_IDEMPOTENCY_TTL_SECS)
+        .map(|secs| secs
+            .parse()
+            .expect("idempotency ttl must be a number of seconds"))
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_IDEMPOTENCY_TTL_SECS));
+    static ref AUTH_LAYER: AuthLayer = {
+        let auth_mode = env::var(ENV_AUTH_MODE).unwrap_or_else(|_| DEFAULT_AUTH_MODE.to_string());
+        let audience = env::var(ENV_JWT_AUDIENCE).ok();
+        let issuer = env::var(ENV_JWT_ISSUER).ok();
+
+        match (auth_mode.as_str(), env::var(ENV_JWT_PUBLIC_KEY_PATH)) {
+            ("header", _) => {
+                warn!(
+                    "trusting the {} header as is, requests must be authenticated upstream",
+                    &*UID_HEADER
+                );
+
+                AuthLayer::header(&UID_HEADER)
+            }
+            ("jwt", Ok(path)) => {
+                let pem = fs::read(&path)
+                    .unwrap_or_else(|err| panic!("reading jwt public key from {}: {}", path, err));
+
+                AuthLayer::rs256(&UID_HEADER, &pem, audience.as_deref(), issuer.as_deref())
+                    .expect("jwt public key must be a valid rsa pem")
+            }
+            ("jwt", Err(_)) => {
+                let secret =
+                    env::var(ENV_JWT_SECRET).expect("jwt secret or public key path must be set");
+
+                AuthLayer::hs256(
+                    &UID_HEADER,
+                    secret.as_bytes(),
+                    audience.as_deref(),
+                    issuer.as_deref(),
+                )
+            }
+            (auth_mode, _) => panic!("auth mode must be either jwt or header, got {}", auth_mode),
+        }
+    };
+}
+
+/// Returns the CORS policy browsers must comply with when performing gRPC-Web requests, as configured by the
+/// environment. Allowed origins are a comma separated list, or `*` for any.
+fn cors_layer() -> CorsLayer {
+    let allowed_origins = env::var(ENV_CORS_ALLOWED_ORIGINS)
+        .unwrap_or_else(|_| DEFAULT_CORS_ALLOWED_ORIGINS.to_string());
+
+    let allow_origin = match allowed_origins.trim() {
+        "*" => AllowOrigin::any(),
+        origins => AllowOrigin::list(origins.split(',').map(|origin| {
+            origin
+                .trim()
+                .parse::<HeaderValue>()
+                .unwrap_or_else(|err| panic!("parsing cors allowed origin {}: {}", origin, err))
+        })),
+    };
+
+    let max_age = env::var(ENV_CORS_MAX_AGE_SECS)
+        .map(|secs| {
+            secs.parse()
+                .expect("cors max age must be a number of seconds")
+        })
+        .map(Duration::from_secs)
+        .unwrap_or(Duration::from_secs(DEFAULT_CORS_MAX_AGE_SECS));
+
+    let uid_header = UID_HEADER
+        .parse::<HeaderName>()
+        .unwrap_or_else(|err| panic!("parsing uid header {}: {}", &*UID_HEADER, err));
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([
+            Method::GET,
+            Method::PUT,
+            Method::DELETE,
+            Method::POST,
+            Method::OPTIONS,
+        ])
+        .allow_headers(
+            CORS_ALLOWED_HEADERS
+                .into_iter()
+                .map(HeaderName::from_static)
+                .chain([uid_header])
+                .collect::<Vec<_>>(),
+        )
+        .expose_headers(CORS_EXPOSED_HEADERS.map(HeaderName::from_static))
+        .max_age(max_age)
+}
+
+/// Returns the rate limits requests are subject to, as configured by the environment. Quotas follow the
+/// `requests/seconds` format, while method quotas are a comma separated list of `/package.Service/Method=quota`.
+fn rate_limits() -> RateLimits {
+    let quota = |env_name: &str, default: &str| -> Quota {
+        let quota = env::var(env_name).unwrap_or_else(|_| default.to_string());
+        quota
+            .parse()
+            .unwrap_or_else(|err| panic!("parsing {} quota {}: {}", env_name, quota, err))
+    };
+
+    let methods = env::var(ENV_RATE_LIMIT_METHODS)
+        .unwrap_or_default()
+        .split(',')
+        .filter(|method| !method.trim().is_empty())
+        .map(|method| {
+            let (path, quota) = method.split_once('=').unwrap_or_else(|| {
+                panic!("method quota {} must be in the path=quota format", method)
+            });
+
+            let quota = quota
+                .parse()
+                .unwrap_or_else(|err| panic!("parsing quota of method {}: {}", path, err));
+
+            (path.trim().to_string(), quota)
+        })
+        .collect();
+
+    RateLimits {
+        global: quota(ENV_RATE_LIMIT_GLOBAL, DEFAULT_RATE_LIMIT_GLOBAL),
+        user: quota(ENV_RATE_LIMIT_USER, DEFAULT_RATE_LIMIT_USER),
+        methods,
+    }
+}
+
+/// Periodically reports the server as serving if, and only if, both the surreal client and the rabbitmq
+/// channel are usable.
+async fn report_health(mut health_reporter: HealthReporter) {
+    let mut interval = tokio::time::interval(*HEALTH_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let surreal_ok = match SURREAL_CLIENT.get().await.version().await {
+            Ok(_) => true,
+            Err(err) => {
+                warn!("checking surreal health: {}", err);
+                false
+            }
+        };
+
+        // the connection may still be alive while the very channel events are published through is not
+        let channel_status = RABBITMQ_CONN.get().await.status();
+        let rabbitmq_ok = channel_status.connected();
+        if !rabbitmq_ok {
+            warn!(
+                "checking rabbitmq health: channel is {:?}",
+                channel_status.state()
+            );
+        }
+
+        let status = if surreal_ok && rabbitmq_ok {
+            ServingStatus::Serving
+        } else {
+            ServingStatus::NotServing
+        };
+
+        health_reporter.set_service_status("", status).await;
+        for service_name in SERVICE_NAMES {
+            health_reporter
+                .set_service_status(service_name, status)
+                .await;
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    env_logger::init();
+    correlation::init_logger();
 
     if let Err(err) = dotenv::dotenv() {
         warn!("processing dotenv file {}", err);
@@ -141,6 +384,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
         client: SURREAL_CLIENT.get().await,
     });
 
+    project_repo
+        .define_indexes()
+        .await
+        .expect("defining the indexes of the project repository");
+
     let file_event_bus: Arc<RabbitMqFileBus> = Arc::new(RabbitMqFileBus {
         channel: RABBITMQ_CONN.get().await,
         app_id: &APP_ID,
@@ -148,20 +396,71 @@ async fn main() -> Result<(), Box<dyn Error>> {
         exchange: &RABBITMQ_FILES_EXCHANGE,
     });
 
+    let (changes, _) = broadcast::channel(*WATCH_CAPACITY);
     let project_app = ProjectApplication {
         project_repo: project_repo.clone(),
         event_bus: file_event_bus.clone(),
+        changes: changes.clone(),
+        idempotency_ttl: *IDEMPOTENCY_TTL,
     };
 
-    let project_server = GrpcProjectServer {
+    let share_app = ShareApplication {
+        project_repo: project_repo.clone(),
+        token_service: Arc::new(JwtTokenService::from_secret(SHARE_TOKEN_SECRET.as_bytes())),
+    };
+
+    let template_repo = Arc::new(SurrealTemplateRepository {
+        client: SURREAL_CLIENT.get().await,
+    });
+
+    let template_app = TemplateApplication {
+        template_repo,
+        project_repo: project_repo.clone(),
+    };
+
+    // the very same server serves every version of the service
+    let project_server = Arc::new(GrpcProjectServer {
         project_app,
-        uid_header: &UID_HEADER,
+        share_app,
+        template_app,
+    });
+
+    let character_server = GrpcCharacterServer {
+        character_app: CharacterApplication {
+            character_repo: Arc::new(SurrealCharacterRepository {
+                client: SURREAL_CLIENT.get().await,
+            }),
+            project_app: ProjectApplication {
+                project_repo: project_repo.clone(),
+                event_bus: file_event_bus.clone(),
+                changes,
+                idempotency_ttl: *IDEMPOTENCY_TTL,
+            },
+        },
     };
 
+    let (health_reporter, health_server) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(health_reporter));
+
+    let reflection_server = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build()?;
+
     let addr = SERVER_ADDR.parse().unwrap();
     info!("server listening on {}", addr);
     Server::builder()
-        .add_service(ProjectServiceServer::new(project_server))
+        .accept_http1(true)
+        .layer(cors_layer())
+        .layer(GrpcWebLayer::new())
+        .layer(RequestIdLayer)
+        .layer(AUTH_LAYER.clone())
+        .layer(RateLimitLayer::new(rate_limits()))
+        .add_service(health_server)
+        .add_service(reflection_server)
+        .add_service(ProjectServiceServer::from_arc(project_server.clone()))
+        .add_service(v1::ProjectServiceServer::from_arc(project_server))
+        .add_service(CharacterServiceServer::new(character_server))
         .serve(addr)
         .await?;
     Ok(())
@@ -0,0 +1,469 @@
+//! Application layer of the character entity.
+
+use super::domain::Character;
+use crate::{
+    metadata::domain::Metadata,
+    project::{
+        application::{EventBus, ProjectApplication, ProjectRepository},
+        domain::{Project, Role},
+    },
+    result::{Error, Result},
+};
+use std::sync::Arc;
+
+#[async_trait::async_trait]
+pub trait CharacterRepository {
+    /// Returns the character with the given id, regardless of the members of its project.
+    async fn find(&self, id: &str) -> Result<Character>;
+    /// Returns all the characters of the given project, sorted by name.
+    async fn find_all(&self, project_id: &str) -> Result<Vec<Character>>;
+    async fn create(&self, character: &mut Character) -> Result<()>;
+    async fn update(&self, character: &Character) -> Result<()>;
+    async fn delete(&self, character: &Character) -> Result<()>;
+}
+
+#[derive(Default, Clone)]
+pub struct CreateOptions {
+    pub aliases: Vec<String>,
+    pub description: String,
+    pub birth: Option<String>,
+    pub death: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Determines the fields to be overwritten by an update. Unset fields are left untouched, while birth and
+/// death may be set to none in order to clear them.
+#[derive(Default, Clone)]
+pub struct UpdateOptions {
+    pub name: Option<String>,
+    pub aliases: Option<Vec<String>>,
+    pub description: Option<String>,
+    pub birth: Option<Option<String>>,
+    pub death: Option<Option<String>>,
+    pub tags: Option<Vec<String>>,
+}
+
+pub struct CharacterApplication<C: CharacterRepository, P: ProjectRepository, B: EventBus> {
+    pub character_repo: Arc<C>,
+    pub project_app: ProjectApplication<P, B>,
+}
+
+impl<C: CharacterRepository, P: ProjectRepository, B: EventBus> CharacterApplication<C, P, B> {
+    pub async fn get(&self, id: &str, uid: &str) -> Result<Character> {
+        info!("processing a \"get\" character request for user {uid}");
+        self.find_with_role(id, uid, Role::Viewer)
+            .await
+            .map(|(character, _)| character)
+    }
+
+    /// Returns all the characters of the project with the given id, as long as the given user is any of its
+    /// members.
+    pub async fn list(&self, project_id: &str, uid: &str) -> Result<Vec<Character>> {
+        info!("processing a \"list\" characters request for user {uid}");
+
+        self.project_app
+            .find_with_role(project_id, uid, Role::Viewer)
+            .await?;
+        self.character_repo.find_all(project_id).await
+    }
+
+    pub async fn create(
+        &self,
+        project_id: &str,
+        name: &str,
+        uid: &str,
+        options: CreateOptions,
+    ) -> Result<Character> {
+        info!("processing a \"create\" character request for user {uid}");
+
+        if name.is_empty() {
+            return Err(Error::MissingFields("name"));
+        }
+
+        let mut project = self
+            .project_app
+            .find_with_role(project_id, uid, Role::Editor)
+            .await?;
+
+        // characters belong to the owner of their project, as any other of its child entities
+        let mut character = Character {
+            id: "".to_string(),
+            project_id: project.id().to_string(),
+            name: name.to_string(),
+            aliases: options.aliases,
+            description: options.description,
+            birth: options.birth,
+            death: options.death,
+            tags: options.tags,
+            meta: Metadata::new(project.meta().created_by().unwrap_or_default()),
+        };
+
+        self.character_repo.create(&mut character).await?;
+        self.touch(&mut project).await;
+        Ok(character)
+    }
+
+    pub async fn update(&self, id: &str, uid: &str, options: UpdateOptions) -> Result<Character> {
+        info!("processing a \"update\" character request for user {uid}");
+
+        if options.name.as_deref() == Some("") {
+            return Err(Error::MissingFields("name"));
+        }
+
+        let (mut character, mut project) = self.find_with_role(id, uid, Role::Editor).await?;
+        if let Some(name) = options.name {
+            character.name = name;
+        }
+
+        if let Some(aliases) = options.aliases {
+            character.aliases = aliases;
+        }
+
+        if let Some(description) = options.description {
+            character.description = description;
+        }
+
+        if let Some(birth) = options.birth {
+            character.birth = birth;
+        }
+
+        if let Some(death) = options.death {
+            character.death = death;
+        }
+
+        if let Some(tags) = options.tags {
+            character.tags = tags;
+        }
+
+        character.meta.touch();
+        self.character_repo.update(&character).await?;
+        self.touch(&mut project).await;
+        Ok(character)
+    }
+
+    pub async fn delete(&self, id: &str, uid: &str) -> Result<()> {
+        info!("processing a \"delete\" character request for user {uid}");
+
+        let (character, mut project) = self.find_with_role(id, uid, Role::Editor).await?;
+        self.character_repo.delete(&character).await?;
+        self.touch(&mut project).await;
+        Ok(())
+    }
+
+    /// Returns the character with the given id, along with its project, if, and only if, the given user has at
+    /// least the given role over the latter.
+    async fn find_with_role(
+        &self,
+        id: &str,
+        uid: &str,
+        role: Role,
+    ) -> Result<(Character, Project)> {
+        let character = self.character_repo.find(id).await?;
+        let project = self
+            .project_app
+            .find_with_role(&character.project_id, uid, role)
+            .await?;
+
+        Ok((character, project))
+    }
+
+    /// Marks the given project as updated, since one of its characters has changed.
+    async fn touch(&self, project: &mut Project) {
+        // the character has already changed, so failing here would only make the client retry it
+        if let Err(err) = self.project_app.touch(project).await {
+            warn!("{} touching project {}", err, project.id());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::{
+        application::CreateOptions as ProjectCreateOptions,
+        domain::ChangeKind,
+        mock::{self, InMemoryProjectRepository, RecordingEventBus},
+    };
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const OWNER: &str = "owner";
+    const EDITOR: &str = "editor";
+    const VIEWER: &str = "viewer";
+    const STRANGER: &str = "stranger";
+
+    #[derive(Default)]
+    struct InMemoryCharacterRepository {
+        characters: Mutex<HashMap<String, Character>>,
+    }
+
+    #[async_trait::async_trait]
+    impl CharacterRepository for InMemoryCharacterRepository {
+        async fn find(&self, id: &str) -> Result<Character> {
+            self.characters
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or(Error::NotFound)
+        }
+
+        async fn find_all(&self, project_id: &str) -> Result<Vec<Character>> {
+            let mut characters: Vec<_> = self
+                .characters
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|character| character.project_id == project_id)
+                .cloned()
+                .collect();
+
+            characters.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(characters)
+        }
+
+        async fn create(&self, character: &mut Character) -> Result<()> {
+            let mut characters = self.characters.lock().unwrap();
+            character.id = format!("character:{}", characters.len() + 1);
+            characters.insert(character.id.clone(), character.clone());
+            Ok(())
+        }
+
+        async fn update(&self, character: &Character) -> Result<()> {
+            self.characters
+                .lock()
+                .unwrap()
+                .insert(character.id.clone(), character.clone());
+            Ok(())
+        }
+
+        async fn delete(&self, character: &Character) -> Result<()> {
+            self.characters.lock().unwrap().remove(&character.id);
+            Ok(())
+        }
+    }
+
+    struct Setup {
+        project: Project,
+        project_repo: Arc<InMemoryProjectRepository>,
+        event_bus: Arc<RecordingEventBus>,
+        character_app: CharacterApplication<
+            InMemoryCharacterRepository,
+            InMemoryProjectRepository,
+            RecordingEventBus,
+        >,
+    }
+
+    /// Returns a character application along with a project shared with an editor and a viewer.
+    async fn setup() -> Setup {
+        let project_repo = Arc::new(InMemoryProjectRepository::default());
+        let event_bus = Arc::new(RecordingEventBus::default());
+        let project_app = mock::project_application(project_repo.clone(), event_bus.clone());
+
+        let project = project_app
+            .create("project", OWNER, ProjectCreateOptions::default())
+            .await
+            .unwrap();
+
+        for (user_id, role) in [(EDITOR, Role::Editor), (VIEWER, Role::Viewer)] {
+            project_app
+                .share(project.id(), user_id, role, OWNER)
+                .await
+                .unwrap();
+        }
+
+        let project = project_repo.get(project.id()).unwrap();
+        Setup {
+            project,
+            project_repo,
+            event_bus,
+            character_app: CharacterApplication {
+                character_repo: Arc::new(InMemoryCharacterRepository::default()),
+                project_app,
+            },
+        }
+    }
+
+    fn rename(name: &str) -> UpdateOptions {
+        UpdateOptions {
+            name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn editors_and_owners_can_change_characters() {
+        let Setup {
+            project,
+            character_app,
+            ..
+        } = setup().await;
+
+        for uid in [EDITOR, OWNER] {
+            let character = character_app
+                .create(project.id(), "Frodo", uid, CreateOptions::default())
+                .await
+                .unwrap();
+
+            assert_eq!(character.project_id(), project.id());
+            assert_eq!(character.meta().created_by(), Some(OWNER));
+
+            let character = character_app
+                .update(character.id(), uid, rename("Bilbo"))
+                .await
+                .unwrap();
+
+            assert_eq!(character.name(), "Bilbo");
+            assert_eq!(character_app.delete(character.id(), uid).await, Ok(()));
+            assert_eq!(
+                character_app.get(character.id(), uid).await.unwrap_err(),
+                Error::NotFound
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn viewers_cannot_change_characters() {
+        let Setup {
+            project,
+            character_app,
+            ..
+        } = setup().await;
+
+        assert_eq!(
+            character_app
+                .create(project.id(), "Frodo", VIEWER, CreateOptions::default())
+                .await
+                .unwrap_err(),
+            Error::Unauthorized
+        );
+
+        let character = character_app
+            .create(project.id(), "Frodo", EDITOR, CreateOptions::default())
+            .await
+            .unwrap();
+
+        assert!(character_app.get(character.id(), VIEWER).await.is_ok());
+        assert_eq!(
+            character_app
+                .list(project.id(), VIEWER)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert_eq!(
+            character_app
+                .update(character.id(), VIEWER, rename("Bilbo"))
+                .await
+                .unwrap_err(),
+            Error::Unauthorized
+        );
+
+        assert_eq!(
+            character_app
+                .delete(character.id(), VIEWER)
+                .await
+                .unwrap_err(),
+            Error::Unauthorized
+        );
+
+        assert_eq!(
+            character_app
+                .get(character.id(), OWNER)
+                .await
+                .unwrap()
+                .name(),
+            "Frodo"
+        );
+    }
+
+    #[tokio::test]
+    async fn non_members_cannot_access_characters() {
+        let Setup {
+            project,
+            character_app,
+            ..
+        } = setup().await;
+
+        let character = character_app
+            .create(project.id(), "Frodo", OWNER, CreateOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            character_app
+                .get(character.id(), STRANGER)
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+
+        assert_eq!(
+            character_app
+                .list(project.id(), STRANGER)
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+
+        assert_eq!(
+            character_app
+                .create(project.id(), "Sam", STRANGER, CreateOptions::default())
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+
+        assert_eq!(
+            character_app
+                .update(character.id(), STRANGER, rename("Bilbo"))
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+
+        assert_eq!(
+            character_app
+                .delete(character.id(), STRANGER)
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn changing_characters_touches_their_project() {
+        let Setup {
+            project,
+            project_repo,
+            event_bus,
+            character_app,
+        } = setup().await;
+
+        let mut changes = character_app.project_app.changes.subscribe();
+        let character = character_app
+            .create(project.id(), "Frodo", EDITOR, CreateOptions::default())
+            .await
+            .unwrap();
+
+        character_app
+            .update(character.id(), EDITOR, rename("Bilbo"))
+            .await
+            .unwrap();
+
+        character_app.delete(character.id(), EDITOR).await.unwrap();
+
+        let touched = project_repo.get(project.id()).unwrap();
+        assert_eq!(touched.version(), project.version() + 3);
+        assert!(touched.meta().updated_at() > project.meta().updated_at());
+        assert_eq!(event_bus.updated.lock().unwrap().len(), 3);
+
+        for _ in 0..3 {
+            let change = changes.try_recv().unwrap();
+            assert_eq!(change.kind, ChangeKind::Updated);
+            assert_eq!(change.project.id(), project.id());
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Domain layer of the character entity.
+
+use crate::metadata::domain::Metadata;
+
+/// Represents a character of a project
+#[derive(Debug, Clone)]
+pub struct Character {
+    pub(super) id: String,
+    pub(super) project_id: String,
+    pub(super) name: String,
+    pub(super) aliases: Vec<String>,
+    pub(super) description: String,
+    pub(super) birth: Option<String>,
+    pub(super) death: Option<String>,
+    pub(super) tags: Vec<String>,
+    pub(super) meta: Metadata,
+}
+
+impl Character {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns when the character was born, as told by the story itself rather than any real calendar.
+    pub fn birth(&self) -> Option<&str> {
+        self.birth.as_deref()
+    }
+
+    /// Same as [`Character::birth`] but for the character's death, if any.
+    pub fn death(&self) -> Option<&str> {
+        self.death.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn meta(&self) -> &Metadata {
+        &self.meta
+    }
+}
@@ -0,0 +1,188 @@
+//! Infrastructure layer for serving the character's aplication as the versioned `agora.character.v1` gRPC
+//! service.
+
+use crate::character::{
+    application::{CharacterApplication, CharacterRepository, CreateOptions, UpdateOptions},
+    domain,
+};
+use crate::grpc;
+use crate::project::application::{EventBus, ProjectRepository};
+use crate::result::Error;
+use tonic::{Request, Response, Status};
+
+// Import the generated rust code into module
+mod proto {
+    tonic::include_proto!("agora.character.v1");
+}
+
+// Proto generated server traits
+use proto::character_service_server::CharacterService;
+pub use proto::character_service_server::CharacterServiceServer;
+
+// Proto message structs
+use proto::{
+    Character, CreateCharacterRequest, DeleteCharacterRequest, GetCharacterRequest,
+    ListCharactersRequest, ListCharactersResponse, UpdateCharacterRequest,
+};
+
+/// Paths of a character an update mask may contain. An empty mask stands for all of them.
+const UPDATABLE_PATHS: [&str; 6] = ["name", "aliases", "description", "birth", "death", "tags"];
+
+pub struct GrpcCharacterServer<
+    C: CharacterRepository + Sync + Send,
+    P: ProjectRepository + Sync + Send,
+    B: EventBus + Sync + Send,
+> {
+    pub character_app: CharacterApplication<C, P, B>,
+}
+
+#[tonic::async_trait]
+impl<
+        C: 'static + CharacterRepository + Sync + Send,
+        P: 'static + ProjectRepository + Sync + Send,
+        B: 'static + EventBus + Sync + Send,
+    > CharacterService for GrpcCharacterServer<C, P, B>
+{
+    async fn get_character(
+        &self,
+        request: Request<GetCharacterRequest>,
+    ) -> Result<Response<Character>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.character_app
+            .get(&msg_ref.id, &uid)
+            .await
+            .map(|character| Response::new(character.into()))
+            .map_err(Into::into)
+    }
+
+    async fn list_characters(
+        &self,
+        request: Request<ListCharactersRequest>,
+    ) -> Result<Response<ListCharactersResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.character_app
+            .list(&msg_ref.project_id, &uid)
+            .await
+            .map(|characters| Response::new(characters.into()))
+            .map_err(Into::into)
+    }
+
+    async fn create_character(
+        &self,
+        request: Request<CreateCharacterRequest>,
+    ) -> Result<Response<Character>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.character_app
+            .create(
+                &msg_ref.project_id,
+                &msg_ref.name,
+                &uid,
+                CreateOptions {
+                    aliases: msg_ref.aliases,
+                    description: msg_ref.description,
+                    birth: msg_ref.birth,
+                    death: msg_ref.death,
+                    tags: msg_ref.tags,
+                },
+            )
+            .await
+            .map(|character| Response::new(character.into()))
+            .map_err(Into::into)
+    }
+
+    async fn update_character(
+        &self,
+        request: Request<UpdateCharacterRequest>,
+    ) -> Result<Response<Character>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let (id, options) = update_options(request.into_inner())?;
+
+        self.character_app
+            .update(&id, &uid, options)
+            .await
+            .map(|character| Response::new(character.into()))
+            .map_err(Into::into)
+    }
+
+    async fn delete_character(
+        &self,
+        request: Request<DeleteCharacterRequest>,
+    ) -> Result<Response<()>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.character_app
+            .delete(&msg_ref.id, &uid)
+            .await
+            .map(Response::new)
+            .map_err(Into::into)
+    }
+}
+
+impl From<domain::Character> for Character {
+    fn from(value: domain::Character) -> Self {
+        Self {
+            id: value.id,
+            project_id: value.project_id,
+            name: value.name,
+            aliases: value.aliases,
+            description: value.description,
+            birth: value.birth,
+            death: value.death,
+            tags: value.tags,
+        }
+    }
+}
+
+impl From<Vec<domain::Character>> for ListCharactersResponse {
+    fn from(value: Vec<domain::Character>) -> Self {
+        Self {
+            characters: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Returns the id of the character to be updated along with the changes to be applied on it, as determined by
+/// the request's update mask.
+fn update_options(request: UpdateCharacterRequest) -> Result<(String, UpdateOptions), Status> {
+    let Some(character) = request.character else {
+        return Err(Error::MissingFields("character").into());
+    };
+
+    let mut paths = request
+        .update_mask
+        .map(|update_mask| update_mask.paths)
+        .unwrap_or_default();
+
+    if paths.is_empty() {
+        paths = UPDATABLE_PATHS.iter().map(ToString::to_string).collect();
+    }
+
+    let mut options = UpdateOptions::default();
+    for path in paths {
+        match path.as_str() {
+            "name" => options.name = Some(character.name.clone()),
+            "aliases" => options.aliases = Some(character.aliases.clone()),
+            "description" => options.description = Some(character.description.clone()),
+            "birth" => options.birth = Some(character.birth.clone()),
+            "death" => options.death = Some(character.death.clone()),
+            "tags" => options.tags = Some(character.tags.clone()),
+            _ => {
+                warn!(
+                    "{} unknown update mask path {}",
+                    Error::InvalidFormat("update_mask"),
+                    path
+                );
+                return Err(Error::InvalidFormat("update_mask").into());
+            }
+        }
+    }
+
+    Ok((character.id, options))
+}
@@ -0,0 +1,4 @@
+pub mod application;
+pub mod domain;
+pub mod grpc;
+pub mod repository;
@@ -0,0 +1,187 @@
+//! Infrastructure layer for managing characters persistency on SurrealDB.
+
+use super::{application::CharacterRepository, domain::Character};
+use crate::metadata::repository::SurrealMetadata;
+use crate::result::{Error, Result};
+use crate::surreal::{self, record_key};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+
+const TABLENAME: &str = "character";
+const PROJECT_TABLENAME: &str = "project";
+
+const QUERY_FIND_CHARACTER: &str = "SELECT * FROM character WHERE id = $id;";
+
+const QUERY_FIND_ALL_CHARACTERS: &str =
+    "SELECT * FROM character WHERE project = type::thing($project_tb, $project_key) ORDER BY name;";
+
+const QUERY_UPDATE_CHARACTER: &str = "UPDATE type::thing($tb, $key) CONTENT $content;";
+
+const QUERY_DELETE_CHARACTER: &str = "DELETE type::thing($tb, $key);";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SurrealCharacter<'a> {
+    id: Thing,
+    name: Cow<'a, str>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    description: Cow<'a, str>,
+    project: Thing,
+    birth: Option<Cow<'a, str>>,
+    death: Option<Cow<'a, str>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    meta: SurrealMetadata<'a>,
+}
+
+impl<'a> From<SurrealCharacter<'a>> for Character {
+    fn from(value: SurrealCharacter<'a>) -> Self {
+        Character {
+            id: value.id.to_string(),
+            project_id: value.project.to_string(),
+            name: value.name.into(),
+            aliases: value.aliases,
+            description: value.description.into(),
+            birth: value.birth.map(Into::into),
+            death: value.death.map(Into::into),
+            tags: value.tags,
+            meta: value.meta.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SurrealAnonymousCharacter<'a> {
+    name: Cow<'a, str>,
+    aliases: Vec<String>,
+    description: Cow<'a, str>,
+    project: Thing,
+    birth: Option<Cow<'a, str>>,
+    death: Option<Cow<'a, str>>,
+    tags: Vec<String>,
+    meta: SurrealMetadata<'a>,
+}
+
+impl<'a> From<&Character> for SurrealAnonymousCharacter<'a> {
+    fn from(value: &Character) -> Self {
+        SurrealAnonymousCharacter {
+            name: value.name.clone().into(),
+            aliases: value.aliases.clone(),
+            description: value.description.clone().into(),
+            project: Thing::from((PROJECT_TABLENAME, record_key(&value.project_id))),
+            birth: value.birth.clone().map(Into::into),
+            death: value.death.clone().map(Into::into),
+            tags: value.tags.clone(),
+            meta: value.meta.clone().into(),
+        }
+    }
+}
+
+/// Repository for managing characters persistency
+pub struct SurrealCharacterRepository<'a> {
+    pub client: &'a Surreal<Client>,
+}
+
+#[async_trait::async_trait]
+impl<'a> CharacterRepository for SurrealCharacterRepository<'a> {
+    async fn find(&self, id: &str) -> Result<Character> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_CHARACTER)
+            .bind(("id", id))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by id on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_item::<SurrealCharacter, Character>(resp, 0)
+    }
+
+    async fn find_all(&self, project_id: &str) -> Result<Vec<Character>> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_ALL_CHARACTERS)
+            .bind(("project_tb", PROJECT_TABLENAME))
+            .bind(("project_key", record_key(project_id)))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by project on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_items::<SurrealCharacter, Character>(resp, 0)
+    }
+
+    async fn create(&self, character: &mut Character) -> Result<()> {
+        let created: SurrealCharacter = self
+            .client
+            .create(TABLENAME)
+            .content(Into::<SurrealAnonymousCharacter>::into(&*character))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing create query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        character.id = created.id.to_string();
+        Ok(())
+    }
+
+    async fn update(&self, character: &Character) -> Result<()> {
+        self.client
+            .query(QUERY_UPDATE_CHARACTER)
+            .bind(("tb", TABLENAME))
+            .bind(("key", record_key(character.id())))
+            .bind((
+                "content",
+                Into::<SurrealAnonymousCharacter>::into(character),
+            ))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing update query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, character: &Character) -> Result<()> {
+        self.client
+            .query(QUERY_DELETE_CHARACTER)
+            .bind(("tb", TABLENAME))
+            .bind(("key", record_key(character.id())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing delete query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+//! Correlation utilities for tracking a single request across logs and emitted events.
+
+use std::future::Future;
+use std::io::Write;
+use uuid::Uuid;
+
+/// Header, both in gRPC metadata and AMQP properties, holding the id of the request.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Maximum length a request id provided by a client may have in order to be continued.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Returns the id of the request being processed by the current task, if any.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Returns the given request id if it is suitable to be continued, or a brand new one otherwise.
+pub fn continue_or_new(request_id: Option<&str>) -> String {
+    request_id
+        .filter(|request_id| {
+            !request_id.is_empty()
+                && request_id.len() <= MAX_REQUEST_ID_LEN
+                && request_id.bytes().all(|byte| byte.is_ascii_graphic())
+        })
+        .map(ToString::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().simple().to_string())
+}
+
+/// Runs the given future with the given request id as the one of the current task.
+pub async fn scope<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Initializes the env logger so every line logged while processing a request includes its id.
+pub fn init_logger() {
+    env_logger::Builder::from_default_env()
+        .format(|buf, record| {
+            let timestamp = buf.timestamp();
+            match current() {
+                Some(request_id) => writeln!(
+                    buf,
+                    "[{} {:<5} {} {}] {}",
+                    timestamp,
+                    record.level(),
+                    record.target(),
+                    request_id,
+                    record.args()
+                ),
+                None => writeln!(
+                    buf,
+                    "[{} {:<5} {}] {}",
+                    timestamp,
+                    record.level(),
+                    record.target(),
+                    record.args()
+                ),
+            }
+        })
+        .init();
+}
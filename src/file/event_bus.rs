@@ -2,10 +2,12 @@
 
 use crate::rabbitmq::EventKind;
 use crate::{
+    correlation::{self, REQUEST_ID_HEADER},
     project::{application::EventBus as ProjectEventBus, domain::Project},
     result::{Error, Result},
 };
 use lapin::options::BasicPublishOptions;
+use lapin::types::{AMQPValue, FieldTable};
 use lapin::{BasicProperties, Channel};
 use serde::{Deserialize, Serialize};
 
@@ -19,6 +21,9 @@ pub struct FileEventPayload<'a> {
     pub(super) file_reference: Option<&'a str>,
     pub(super) event_issuer: &'a str,
     pub(super) event_kind: EventKind,
+    /// Id of the request the event was emitted from, the same as the one in the AMQP headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) request_id: Option<&'a str>,
 }
 
 pub struct RabbitMqFileBus<'a> {
@@ -31,10 +36,31 @@ pub struct RabbitMqFileBus<'a> {
 #[async_trait::async_trait]
 impl<'a> ProjectEventBus for RabbitMqFileBus<'a> {
     async fn emit_file_created(&self, project: &Project) -> Result<()> {
+        self.emit(project, EventKind::Created).await
+    }
+
+    async fn emit_file_updated(&self, project: &Project) -> Result<()> {
+        self.emit(project, EventKind::Updated).await
+    }
+
+    async fn emit_file_deleted(&self, project: &Project) -> Result<()> {
+        self.emit(project, EventKind::Deleted).await
+    }
+
+    async fn emit_file_transferred(&self, project: &Project) -> Result<()> {
+        self.emit(project, EventKind::Transferred).await
+    }
+}
+
+impl<'a> RabbitMqFileBus<'a> {
+    /// Publishes into the exchange an event of the given kind about the file backing the given project.
+    async fn emit(&self, project: &Project, event_kind: EventKind) -> Result<()> {
         let Some(user_id) = project.meta().created_by() else {
-            return Err(Error::MissingFields);
+            return Err(Error::MissingFields("created_by"));
         };
 
+        let kind = event_kind.to_string();
+        let request_id = correlation::current();
         let event = FileEventPayload {
             user_id,
             app_id: self.app_id,
@@ -42,15 +68,25 @@ impl<'a> ProjectEventBus for RabbitMqFileBus<'a> {
             file_id: project.id(),
             file_reference: project.reference(),
             event_issuer: self.issuer,
-            event_kind: EventKind::Created,
+            event_kind,
+            request_id: request_id.as_deref(),
         };
 
+        let mut headers = FieldTable::default();
+        if let Some(request_id) = &request_id {
+            headers.insert(
+                REQUEST_ID_HEADER.into(),
+                AMQPValue::LongString(request_id.as_str().into()),
+            );
+        }
+
         let payload = serde_json::to_string(&event)
             .map(|str| str.into_bytes())
             .map_err(|err| {
                 error!(
-                    "{} serializing \"project created\" event data to json: {}",
+                    "{} serializing \"project {}\" event data to json: {}",
                     Error::Unknown,
+                    kind,
                     err
                 );
                 Error::Unknown
@@ -62,13 +98,14 @@ impl<'a> ProjectEventBus for RabbitMqFileBus<'a> {
                 "",
                 BasicPublishOptions::default(),
                 &payload,
-                BasicProperties::default(),
+                BasicProperties::default().with_headers(headers),
             )
             .await
             .map_err(|err| {
                 error!(
-                    "{} emititng \"project created\" event: {}",
+                    "{} emititng \"project {}\" event: {}",
                     Error::Unknown,
+                    kind,
                     err
                 );
                 Error::Unknown
@@ -76,8 +113,9 @@ impl<'a> ProjectEventBus for RabbitMqFileBus<'a> {
             .await
             .map_err(|err| {
                 error!(
-                    "{} confirming \"project created\" event reception: {}",
+                    "{} confirming \"project {}\" event reception: {}",
                     Error::Unknown,
+                    kind,
                     err
                 );
                 Error::Unknown
@@ -2,10 +2,39 @@
 
 use super::event_bus::FileEventPayload;
 use crate::{
+    correlation,
     project::application::{CreateOptions, EventBus, ProjectApplication, ProjectRepository},
-    rabbitmq::EventHandler,
+    rabbitmq::{EventHandler, EventKind},
     result::{Error, Result},
 };
+use serde::Deserialize;
+
+/// Same as [`FileEventPayload`] but as encoded by legacy producers, which know nothing about request ids.
+#[derive(Deserialize)]
+struct LegacyFileEventPayload<'a> {
+    user_id: &'a str,
+    app_id: &'a str,
+    file_name: &'a str,
+    file_id: &'a str,
+    file_reference: Option<&'a str>,
+    event_issuer: &'a str,
+    event_kind: EventKind,
+}
+
+impl<'a> From<LegacyFileEventPayload<'a>> for FileEventPayload<'a> {
+    fn from(value: LegacyFileEventPayload<'a>) -> Self {
+        FileEventPayload {
+            user_id: value.user_id,
+            app_id: value.app_id,
+            file_name: value.file_name,
+            file_id: value.file_id,
+            file_reference: value.file_reference,
+            event_issuer: value.event_issuer,
+            event_kind: value.event_kind,
+            request_id: None,
+        }
+    }
+}
 
 pub struct FileEventHandler<P: ProjectRepository, B: EventBus> {
     pub issuers_whitelist: &'static [String],
@@ -17,11 +46,29 @@ impl<P: ProjectRepository + Sync + Send, B: EventBus + Sync + Send> EventHandler
     for FileEventHandler<P, B>
 {
     async fn on_event(&self, body: Vec<u8>) -> Result<()> {
-        let payload = bincode::deserialize::<FileEventPayload>(&body).map_err(|err| {
-            warn!("{} deserializing file event body: {}", Error::Unknown, err);
-            Error::Unknown
-        })?;
+        // events are emitted as json, while legacy producers may still be sending them through bincode, which
+        // cannot tell a missing trailing field apart from a truncated body
+        let payload = serde_json::from_slice::<FileEventPayload>(&body)
+            .or_else(|_| bincode::deserialize::<LegacyFileEventPayload>(&body).map(Into::into))
+            .map_err(|err| {
+                warn!("{} deserializing file event body: {}", Error::Unknown, err);
+                Error::Unknown
+            })?;
+
+        // consumers may drop the AMQP headers, while the payload always keeps the id of the request
+        match payload.request_id {
+            Some(request_id) => {
+                let request_id = correlation::continue_or_new(Some(request_id));
+                correlation::scope(request_id, self.handle(payload)).await
+            }
+            None => self.handle(payload).await,
+        }
+    }
+}
 
+impl<P: ProjectRepository + Sync + Send, B: EventBus + Sync + Send> FileEventHandler<P, B> {
+    /// Forwards the given event to its corresponding handler, as long as it comes from a whitelisted issuer.
+    async fn handle<'a>(&self, payload: FileEventPayload<'a>) -> Result<()> {
         if !self
             .issuers_whitelist
             .contains(&payload.event_issuer.to_string())
@@ -32,15 +79,14 @@ impl<P: ProjectRepository + Sync + Send, B: EventBus + Sync + Send> EventHandler
 
         match payload.event_kind {
             crate::rabbitmq::EventKind::Created => self.on_file_created(payload).await,
+            crate::rabbitmq::EventKind::Deleted => self.on_file_deleted(payload).await,
             _ => {
                 warn!("unhandled file {} event", payload.event_kind);
                 Ok(())
             }
         }
     }
-}
 
-impl<P: ProjectRepository, B: EventBus> FileEventHandler<P, B> {
     async fn on_file_created<'a>(&self, event: FileEventPayload<'a>) -> Result<()> {
         info!(
             "handlering a file \"created\" event from issuer {}",
@@ -60,4 +106,19 @@ impl<P: ProjectRepository, B: EventBus> FileEventHandler<P, B> {
 
         Ok(())
     }
+
+    async fn on_file_deleted<'a>(&self, event: FileEventPayload<'a>) -> Result<()> {
+        info!(
+            "handlering a file \"deleted\" event from issuer {}",
+            event.event_issuer
+        );
+
+        match self.project_app.delete_by_reference(event.file_id).await {
+            Err(Error::NotFound) => {
+                info!("no project references the deleted file {}", event.file_id);
+                Ok(())
+            }
+            other => other,
+        }
+    }
 }
@@ -1,23 +1,80 @@
-//! gRPC utilities for managing request's headers and conversions.
+//! gRPC utilities for managing request's headers, authentication and conversions.
 
-use crate::result::Error;
-use tonic::{Request, Status};
+use crate::correlation::{self, REQUEST_ID_HEADER};
+use crate::result::{Error, Result as AppResult};
+use jsonwebtoken::{errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use prost::Message;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::http::{self, HeaderMap, HeaderValue};
+use tonic::codegen::BoxFuture;
+use tonic::{Code, Request, Status};
+use tonic_types::{pb, ErrorDetails, StatusExt};
+use tower::{Layer, Service};
+
+pub mod rate_limit;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Encoded descriptors of all the messages and services in the protos of the crate, including all their
+/// versions, as required by server reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("agora_descriptor");
+
+/// Services that are neither authenticated nor limited, such as health checking and reflection.
+const INTERNAL_PATH_PREFIX: &str = "/grpc.";
+const AUTHORIZATION_HEADER: &str = "authorization";
+const BEARER_PREFIX: &str = "Bearer ";
+const ERROR_DOMAIN: &str = "agora";
 
 impl From<Error> for Status {
     fn from(value: Error) -> Self {
+        let code = match value {
+            Error::Unknown => Code::Unknown,
+            Error::NotFound => Code::NotFound,
+            Error::NotAvailable => Code::Unavailable,
+            Error::Unauthorized => Code::PermissionDenied,
+            Error::InvalidToken | Error::InvalidFormat(_) | Error::InvalidHeader => {
+                Code::InvalidArgument
+            }
+            Error::WrongCredentials => Code::Unauthenticated,
+            Error::RegexNotMatch => Code::FailedPrecondition,
+            Error::AlreadyExists => Code::AlreadyExists,
+            Error::MissingFields(_) => Code::InvalidArgument,
+            Error::Conflict => Code::Aborted,
+            Error::RateLimited => Code::ResourceExhausted,
+        };
+
+        let mut details = ErrorDetails::with_error_info(
+            value.reason(),
+            ERROR_DOMAIN,
+            HashMap::from([("code".to_string(), value.to_string())]),
+        );
+
         match value {
-            Error::Unknown => Status::unknown(value),
-            Error::NotFound => Status::not_found(value),
-            Error::NotAvailable => Status::unavailable(value),
-            Error::Unauthorized => Status::permission_denied(value),
-            Error::InvalidToken | Error::InvalidFormat | Error::InvalidHeader => {
-                Status::invalid_argument(value)
+            Error::InvalidFormat(field) => {
+                details.add_bad_request_violation(field, "the field has an invalid format");
+            }
+            Error::MissingFields(field) => {
+                details.add_bad_request_violation(field, "the field is required");
             }
-            Error::WrongCredentials => Status::unauthenticated(value),
-            Error::RegexNotMatch => Status::failed_precondition(value),
-            Error::AlreadyExists => Status::already_exists(value),
-            Error::MissingFields => Status::invalid_argument(value),
+            _ => {}
         }
+
+        Status::with_error_details(code, value, details)
+    }
+}
+
+impl From<Error> for pb::Status {
+    fn from(value: Error) -> Self {
+        // the details of the status are the very same google.rpc.Status, already encoded
+        let status = Status::from(value);
+        pb::Status::decode(status.details()).unwrap_or_else(|_| pb::Status {
+            code: status.code() as i32,
+            message: status.message().to_string(),
+            details: Vec::new(),
+        })
     }
 }
 
@@ -39,3 +96,263 @@ pub fn get_header<T>(req: &Request<T>, header: &str) -> Result<String, Status> {
         Error::InvalidHeader.into()
     })
 }
+
+/// Same as [`get_header`] but returning none instead of an error if the header is missing.
+pub fn get_optional_header<T>(req: &Request<T>, header: &str) -> Result<Option<String>, Status> {
+    if req.metadata().get(header).is_none() {
+        return Ok(None);
+    }
+
+    get_header(req, header).map(Some)
+}
+
+/// Given a gRPC request, returns the uid of the user performing it, as authenticated by the [`AuthLayer`].
+/// Otherwise the request carries no credentials at all and an error is returned.
+pub fn get_uid<T>(req: &Request<T>) -> Result<String, Status> {
+    req.extensions()
+        .get::<Uid>()
+        .map(|uid| uid.0.clone())
+        .ok_or_else(|| {
+            warn!("{} request carries no credentials", Error::WrongCredentials);
+            Error::WrongCredentials.into()
+        })
+}
+
+/// Uid of the user performing a request, as inserted into its extensions by the [`AuthLayer`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Uid(pub String);
+
+/// Determines the claims required from any bearer token.
+#[derive(Deserialize)]
+struct AuthClaims {
+    sub: String,
+}
+
+/// Layer authenticating every request once, exposing the uid of its user through the request extensions so
+/// any inner layer and handler can read it through [`get_uid`]. Requests with no authorization at all go
+/// through with no uid, so only those endpoints requiring no user may succeed.
+#[derive(Clone)]
+pub struct AuthLayer {
+    uid_header: &'static str,
+    verifier: Option<(DecodingKey, Validation)>,
+}
+
+impl AuthLayer {
+    /// Returns a layer trusting the uid header as is. Only suitable when the service is reachable through a
+    /// proxy that authenticates requests by itself.
+    pub fn header(uid_header: &'static str) -> Self {
+        Self {
+            uid_header,
+            verifier: None,
+        }
+    }
+
+    /// Returns a layer verifying bearer tokens signed through HS256 with the given secret.
+    pub fn hs256(
+        uid_header: &'static str,
+        secret: &[u8],
+        audience: Option<&str>,
+        issuer: Option<&str>,
+    ) -> Self {
+        Self {
+            uid_header,
+            verifier: Some((
+                DecodingKey::from_secret(secret),
+                validation(Algorithm::HS256, audience, issuer),
+            )),
+        }
+    }
+
+    /// Returns a layer verifying bearer tokens signed through RS256 with the private counterpart of the given
+    /// PEM-encoded public key.
+    pub fn rs256(
+        uid_header: &'static str,
+        pem: &[u8],
+        audience: Option<&str>,
+        issuer: Option<&str>,
+    ) -> AppResult<Self> {
+        let decoding_key = DecodingKey::from_rsa_pem(pem).map_err(|err| {
+            error!(
+                "{} parsing rsa public key: {}",
+                Error::InvalidFormat("public_key"),
+                err
+            );
+            Error::InvalidFormat("public_key")
+        })?;
+
+        Ok(Self {
+            uid_header,
+            verifier: Some((decoding_key, validation(Algorithm::RS256, audience, issuer))),
+        })
+    }
+
+    /// Returns the uid of the user performing a request with the given headers, if any. Fails if the request
+    /// carries an invalid bearer token.
+    fn uid(&self, headers: &HeaderMap) -> Result<Option<String>, Status> {
+        let Some((decoding_key, validation)) = &self.verifier else {
+            return headers
+                .get(self.uid_header)
+                .map(|uid| {
+                    uid.to_str().map(ToString::to_string).map_err(|err| {
+                        warn!("{} parsing uid header: {}", Error::InvalidHeader, err);
+                        Into::<Status>::into(Error::InvalidHeader)
+                    })
+                })
+                .transpose();
+        };
+
+        let Some(authorization) = headers.get(AUTHORIZATION_HEADER) else {
+            return Ok(None);
+        };
+
+        let token = authorization
+            .to_str()
+            .ok()
+            .and_then(|authorization| authorization.strip_prefix(BEARER_PREFIX))
+            .ok_or_else(|| {
+                warn!("{} parsing authorization header", Error::InvalidToken);
+                Into::<Status>::into(Error::InvalidToken)
+            })?;
+
+        jsonwebtoken::decode::<AuthClaims>(token, decoding_key, validation)
+            .map(|data| Some(data.claims.sub))
+            .map_err(|err| {
+                let error = match err.kind() {
+                    ErrorKind::InvalidToken
+                    | ErrorKind::Base64(_)
+                    | ErrorKind::Json(_)
+                    | ErrorKind::Utf8(_) => Error::InvalidToken,
+                    _ => Error::WrongCredentials,
+                };
+
+                warn!("{} verifying bearer token: {}", error, err);
+                error.into()
+            })
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service wrapped by an [`AuthLayer`].
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    layer: AuthLayer,
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        if request.uri().path().starts_with(INTERNAL_PATH_PREFIX) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let uid = match self.layer.uid(request.headers()) {
+            Ok(uid) => uid,
+            Err(status) => return Box::pin(async move { Ok(status.to_http()) }),
+        };
+
+        if let Some(uid) = uid {
+            request.extensions_mut().insert(Uid(uid));
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Returns the validation rules for bearer tokens signed through the given algorithm, which must include a
+/// subject and an expiration time.
+fn validation(algorithm: Algorithm, audience: Option<&str>, issuer: Option<&str>) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.set_required_spec_claims(&["exp", "sub"]);
+
+    if let Some(audience) = audience {
+        validation.set_audience(&[audience]);
+    }
+
+    if let Some(issuer) = issuer {
+        validation.set_issuer(&[issuer]);
+    }
+
+    validation
+}
+
+/// Layer scoping every request under its `x-request-id`, which is either continued from the request itself
+/// or brand new, and echoing it back in the response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+/// Service wrapped by a [`RequestIdLayer`].
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S, B, R> Service<http::Request<B>> for RequestIdService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        let request_id = correlation::continue_or_new(
+            request
+                .headers()
+                .get(REQUEST_ID_HEADER)
+                .and_then(|request_id| request_id.to_str().ok()),
+        );
+
+        // continued or brand new ids are always valid header values
+        let header = HeaderValue::from_str(&request_id).ok();
+        if let Some(header) = &header {
+            request
+                .headers_mut()
+                .insert(REQUEST_ID_HEADER, header.clone());
+        }
+
+        let future = self.inner.call(request);
+        Box::pin(correlation::scope(request_id, async move {
+            let mut response = future.await?;
+            if let Some(header) = header {
+                response.headers_mut().insert(REQUEST_ID_HEADER, header);
+            }
+
+            Ok(response)
+        }))
+    }
+}
@@ -0,0 +1,468 @@
+//! Rate limiting of gRPC requests through token buckets, both per caller and global.
+
+use super::{Uid, INTERNAL_PATH_PREFIX};
+use crate::result::Error;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::body::BoxBody;
+use tonic::codegen::http::{self, HeaderValue};
+use tonic::codegen::BoxFuture;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Status;
+use tower::{Layer, Service};
+
+pub const RETRY_AFTER_HEADER: &str = "retry-after";
+
+/// Amount of buckets above which callers no longer get one of their own, but share a single one per method.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+/// Amount of buckets inserted between two evictions of those that are full, since they are no different from
+/// new ones.
+const EVICTION_INTERVAL: usize = 1_000;
+
+/// Determines the amount of requests allowed within a period of time, all at once at most.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    /// Returns the amount of requests regained per second.
+    fn rate(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+impl FromStr for Quota {
+    type Err = Error;
+
+    /// Parses a quota in the `requests/seconds` format, e.g. `20/1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, secs) = s.trim().split_once('/').ok_or_else(|| {
+            warn!("{} parsing quota {}", Error::InvalidFormat("quota"), s);
+            Error::InvalidFormat("quota")
+        })?;
+
+        let requests = requests.trim().parse().map_err(|err| {
+            warn!(
+                "{} parsing quota requests: {}",
+                Error::InvalidFormat("quota"),
+                err
+            );
+            Error::InvalidFormat("quota")
+        })?;
+
+        let secs: u64 = secs.trim().parse().map_err(|err| {
+            warn!(
+                "{} parsing quota seconds: {}",
+                Error::InvalidFormat("quota"),
+                err
+            );
+            Error::InvalidFormat("quota")
+        })?;
+
+        if requests == 0 || secs == 0 {
+            warn!(
+                "{} quota {} allows no request",
+                Error::InvalidFormat("quota"),
+                s
+            );
+            return Err(Error::InvalidFormat("quota"));
+        }
+
+        Ok(Self {
+            requests,
+            period: Duration::from_secs(secs),
+        })
+    }
+}
+
+/// Determines the quotas requests are limited by. The global quota is shared by all requests, while the user
+/// one applies to each caller on its own. Methods, by their full path, may override the user quota.
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    pub global: Quota,
+    pub user: Quota,
+    pub methods: HashMap<String, Quota>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(quota: &Quota, now: Instant) -> Self {
+        Self {
+            tokens: quota.requests as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, quota: &Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * quota.rate()).min(quota.requests as f64);
+        self.updated_at = now;
+    }
+
+    /// Returns how long until the bucket holds a whole token, if it does not already.
+    fn wait(&self, quota: &Quota) -> Option<Duration> {
+        (self.tokens < 1.).then(|| Duration::from_secs_f64((1. - self.tokens) / quota.rate()))
+    }
+
+    fn is_full(&self, quota: &Quota) -> bool {
+        self.tokens >= quota.requests as f64
+    }
+}
+
+/// Represents whoever performs a request, as told apart by the rate limiter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Caller {
+    /// An authenticated user, by uid.
+    User(String),
+    /// An unauthenticated client, by address.
+    Peer(IpAddr),
+    /// Any caller once there is no room for more buckets, or an unauthenticated one with no known address.
+    Unknown,
+}
+
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Caller::User(uid) => write!(f, "user {uid}"),
+            Caller::Peer(addr) => write!(f, "peer {addr}"),
+            Caller::Unknown => write!(f, "unknown caller"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    global: Bucket,
+    /// Buckets by method, if it has a quota of its own, and caller.
+    callers: HashMap<(Option<String>, Caller), Bucket>,
+    /// Amount of buckets inserted since the last eviction.
+    insertions: usize,
+}
+
+impl Buckets {
+    /// Discards all those buckets that are full by the given limits.
+    fn evict(&mut self, limits: &RateLimits, now: Instant) {
+        self.callers.retain(|(method, _), bucket| {
+            let quota = method
+                .as_ref()
+                .and_then(|method| limits.methods.get(method))
+                .unwrap_or(&limits.user);
+
+            bucket.refill(quota, now);
+            !bucket.is_full(quota)
+        });
+
+        self.insertions = 0;
+    }
+}
+
+/// Layer rejecting those requests exceeding any of the configured [`RateLimits`] as resource exhausted,
+/// telling through the `retry-after` header how many seconds to wait before retrying. Callers are told apart
+/// by the [`Uid`] in the request extensions, so the layer must be wrapped by the
+/// [`AuthLayer`](super::AuthLayer), or by their address otherwise.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limits: Arc<RateLimits>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimitLayer {
+    /// Returns a layer limiting requests by the given limits.
+    pub fn new(limits: RateLimits) -> Self {
+        let buckets = Buckets {
+            global: Bucket::new(&limits.global, Instant::now()),
+            callers: HashMap::new(),
+            insertions: 0,
+        };
+
+        Self {
+            limits: Arc::new(limits),
+            buckets: Arc::new(Mutex::new(buckets)),
+        }
+    }
+
+    /// Takes a token from both the global bucket and the one of the given caller for the given method, if, and
+    /// only if, both have any. Otherwise returns how long until they do.
+    fn acquire(&self, method: &str, caller: Caller) -> Result<(), Duration> {
+        let (method, quota) = match self.limits.methods.get(method) {
+            Some(quota) => (Some(method.to_string()), quota),
+            None => (None, &self.limits.user),
+        };
+
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut key = (method, caller);
+        if !buckets.callers.contains_key(&key) {
+            if buckets.insertions >= EVICTION_INTERVAL {
+                buckets.evict(&self.limits, now);
+            }
+
+            if buckets.callers.len() >= MAX_TRACKED_BUCKETS {
+                key.1 = Caller::Unknown;
+            }
+
+            buckets.insertions += 1;
+        }
+
+        let Buckets {
+            global, callers, ..
+        } = &mut *buckets;
+        let bucket = callers
+            .entry(key)
+            .or_insert_with(|| Bucket::new(quota, now));
+
+        global.refill(&self.limits.global, now);
+        bucket.refill(quota, now);
+
+        let wait = [global.wait(&self.limits.global), bucket.wait(quota)]
+            .into_iter()
+            .flatten()
+            .max();
+
+        if let Some(wait) = wait {
+            return Err(wait);
+        }
+
+        global.tokens -= 1.;
+        bucket.tokens -= 1.;
+        Ok(())
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Service wrapped by a [`RateLimitLayer`].
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    layer: RateLimitLayer,
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let method = request.uri().path();
+        if method.starts_with(INTERNAL_PATH_PREFIX) {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let caller = match request.extensions().get::<Uid>() {
+            Some(Uid(uid)) => Caller::User(uid.clone()),
+            None => request
+                .extensions()
+                .get::<TcpConnectInfo>()
+                .and_then(TcpConnectInfo::remote_addr)
+                .map(|addr| Caller::Peer(addr.ip()))
+                .unwrap_or(Caller::Unknown),
+        };
+
+        if let Err(wait) = self.layer.acquire(method, caller.clone()) {
+            let retry_after = retry_after_secs(wait);
+            warn!(
+                "{} {} exceeded the rate limit of {}, retry after {} seconds",
+                Error::RateLimited,
+                caller,
+                method,
+                retry_after
+            );
+
+            let mut response = Status::from(Error::RateLimited).to_http();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER_HEADER, HeaderValue::from(retry_after));
+
+            return Box::pin(async move { Ok(response) });
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+/// Returns the given wait in whole seconds, rounded up so retrying after them never fails for being too early.
+fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota(requests: u32, secs: u64) -> Quota {
+        Quota {
+            requests,
+            period: Duration::from_secs(secs),
+        }
+    }
+
+    fn limits(user: Quota) -> RateLimits {
+        RateLimits {
+            global: quota(u32::MAX, 1),
+            user,
+            methods: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn quotas_are_parsed_from_requests_per_seconds() {
+        assert_eq!(Quota::from_str("20/1"), Ok(quota(20, 1)));
+        assert_eq!(Quota::from_str(" 5 / 60 "), Ok(quota(5, 60)));
+    }
+
+    #[test]
+    fn malformed_quotas_are_rejected() {
+        for s in [
+            "", "20", "/", "a/1", "20/b", "-1/1", "20/1.5", "20/1/1", "0/1", "20/0",
+        ] {
+            assert_eq!(
+                Quota::from_str(s),
+                Err(Error::InvalidFormat("quota")),
+                "quota {s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn buckets_refill_at_the_quota_rate_up_to_its_requests() {
+        let quota = quota(10, 2);
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.,
+            updated_at: start,
+        };
+
+        bucket.refill(&quota, start + Duration::from_secs(1));
+        assert_eq!(bucket.tokens, 5.);
+
+        bucket.refill(&quota, start + Duration::from_millis(1_400));
+        assert!((bucket.tokens - 7.).abs() < 1e-9);
+
+        bucket.refill(&quota, start + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 10.);
+        assert!(bucket.is_full(&quota));
+    }
+
+    #[test]
+    fn buckets_wait_until_they_hold_a_whole_token() {
+        let quota = quota(1, 4);
+        let mut bucket = Bucket::new(&quota, Instant::now());
+        assert_eq!(bucket.wait(&quota), None);
+
+        bucket.tokens = 0.5;
+        assert_eq!(bucket.wait(&quota), Some(Duration::from_secs(2)));
+
+        bucket.tokens = 0.;
+        assert_eq!(bucket.wait(&quota), Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn retry_after_is_rounded_up_to_whole_seconds() {
+        assert_eq!(retry_after_secs(Duration::from_secs(2)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(2_001)), 3);
+        assert_eq!(retry_after_secs(Duration::from_nanos(1)), 1);
+        assert_eq!(retry_after_secs(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn exhausted_callers_are_told_how_long_to_wait() {
+        let layer = RateLimitLayer::new(limits(quota(2, 10)));
+        let caller = || Caller::User("uid".to_string());
+
+        assert_eq!(layer.acquire("/method", caller()), Ok(()));
+        assert_eq!(layer.acquire("/method", caller()), Ok(()));
+
+        let wait = layer.acquire("/method", caller()).unwrap_err();
+        assert!(wait > Duration::from_millis(4_900) && wait <= Duration::from_secs(5));
+        assert_eq!(retry_after_secs(wait), 5);
+    }
+
+    #[test]
+    fn callers_share_a_single_bucket_beyond_the_tracked_ones() {
+        let layer = RateLimitLayer::new(limits(quota(1, 3_600)));
+        for uid in 0..MAX_TRACKED_BUCKETS {
+            assert_eq!(
+                layer.acquire("/method", Caller::User(uid.to_string())),
+                Ok(())
+            );
+        }
+
+        let untracked = Caller::User(MAX_TRACKED_BUCKETS.to_string());
+        assert_eq!(layer.acquire("/method", untracked.clone()), Ok(()));
+        assert!(layer
+            .acquire("/method", Caller::Peer([127, 0, 0, 1].into()))
+            .is_err());
+
+        let buckets = layer.buckets.lock().unwrap();
+        assert!(buckets.callers.contains_key(&(None, Caller::Unknown)));
+        assert!(!buckets.callers.contains_key(&(None, untracked)));
+        assert_eq!(buckets.callers.len(), MAX_TRACKED_BUCKETS + 1);
+    }
+
+    #[test]
+    fn eviction_discards_full_buckets_only() {
+        let limits = limits(quota(1, 10));
+        let start = Instant::now();
+        let mut buckets = Buckets {
+            global: Bucket::new(&limits.global, start),
+            callers: HashMap::from([
+                (
+                    (None, Caller::User("idle".to_string())),
+                    Bucket {
+                        tokens: 0.,
+                        updated_at: start,
+                    },
+                ),
+                (
+                    (None, Caller::User("busy".to_string())),
+                    Bucket {
+                        tokens: 0.,
+                        updated_at: start + Duration::from_secs(15),
+                    },
+                ),
+            ]),
+            insertions: EVICTION_INTERVAL,
+        };
+
+        buckets.evict(&limits, start + Duration::from_secs(20));
+        assert_eq!(buckets.insertions, 0);
+        assert_eq!(buckets.callers.len(), 1);
+        assert!(buckets
+            .callers
+            .contains_key(&(None, Caller::User("busy".to_string()))));
+    }
+}
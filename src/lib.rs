@@ -1,13 +1,16 @@
 #[macro_use]
 extern crate log;
 
+pub mod character;
+pub mod correlation;
 pub mod file;
 pub mod metadata;
 pub mod project;
 #[cfg(feature = "agent")]
 pub mod rabbitmq;
+pub mod template;
 
 #[cfg(feature = "grpc")]
-mod grpc;
+pub mod grpc;
 mod result;
 mod surreal;
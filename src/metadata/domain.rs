@@ -8,6 +8,7 @@ pub struct Metadata {
     pub(super) created_at: Option<SystemTime>,
     pub(super) updated_at: Option<SystemTime>,
     pub(super) deleted_at: Option<SystemTime>,
+    pub(super) previous_owner: Option<String>,
 }
 
 impl Metadata {
@@ -17,6 +18,7 @@ impl Metadata {
             created_at: Some(SystemTime::now()),
             updated_at: None,
             deleted_at: None,
+            previous_owner: None,
         }
     }
 
@@ -35,4 +37,29 @@ impl Metadata {
     pub fn deleted_at(&self) -> Option<SystemTime> {
         self.deleted_at
     }
+
+    pub fn previous_owner(&self) -> Option<&str> {
+        self.previous_owner.as_deref()
+    }
+
+    /// Sets the current time as the last update time.
+    pub fn touch(&mut self) {
+        self.updated_at = Some(SystemTime::now());
+    }
+
+    /// Sets the given user as the new owner, keeping track of the previous one.
+    pub fn transfer(&mut self, owner: &str) {
+        self.previous_owner = self.created_by.replace(owner.to_string());
+        self.touch();
+    }
+
+    /// Marks the metadata as deleted at the current time, without removing any other field.
+    pub fn delete(&mut self) {
+        self.deleted_at = Some(SystemTime::now());
+    }
+
+    /// Clears the deletion mark set by [`Metadata::delete`], if any.
+    pub fn restore(&mut self) {
+        self.deleted_at = None;
+    }
 }
@@ -11,6 +11,7 @@ pub struct SurrealMetadata<'a> {
     pub(super) created_at: Option<Cow<'a, SystemTime>>,
     pub(super) updated_at: Option<Cow<'a, SystemTime>>,
     pub(super) deleted_at: Option<Cow<'a, SystemTime>>,
+    pub(super) previous_owner: Option<Cow<'a, str>>,
 }
 
 impl<'a> From<Metadata> for SurrealMetadata<'a> {
@@ -20,6 +21,7 @@ impl<'a> From<Metadata> for SurrealMetadata<'a> {
             created_at: value.created_at.map(Cow::Owned),
             updated_at: value.updated_at.map(Cow::Owned),
             deleted_at: value.deleted_at.map(Cow::Owned),
+            previous_owner: value.previous_owner.map(Into::into),
         }
     }
 }
@@ -31,6 +33,7 @@ impl<'a> From<SurrealMetadata<'a>> for Metadata {
             created_at: value.created_at.map(|item| item.into_owned()),
             updated_at: value.updated_at.map(|item| item.into_owned()),
             deleted_at: value.deleted_at.map(|item| item.into_owned()),
+            previous_owner: value.previous_owner.map(Into::into),
         }
     }
 }
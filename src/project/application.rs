@@ -1,42 +1,269 @@
 //! Application layer of the project entity.
 
-use super::domain::{Project, ProjectWithCardinalities};
-use crate::{metadata::domain::Metadata, result::Result};
+use super::domain::{
+    ChangeKind, Member, Project, ProjectChange, ProjectPage, ProjectWithCardinalities, Role,
+    ShareToken,
+};
+use crate::{
+    metadata::domain::Metadata,
+    result::{Error, Result},
+    template::domain::{Entity, Template},
+};
+use futures_util::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::DefaultHasher, BTreeMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 100;
+const MAX_BATCH_SIZE: usize = 100;
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 #[async_trait::async_trait]
 pub trait ProjectRepository {
-    async fn find(&self, id: &str, created_by: &str) -> Result<Project>;
-    async fn find_all(&self, created_by: &str) -> Result<Vec<ProjectWithCardinalities>>;
-    async fn create(&self, project: &mut Project) -> Result<()>;
-    async fn update(&self, project: &Project) -> Result<()>;
+    /// Returns the project with the given id if, and only if, the given user is any of its members.
+    async fn find(&self, id: &str, uid: &str) -> Result<Project>;
+    /// Returns the given page of the projects the given user is member of, highlighted projects first.
+    async fn find_all(
+        &self,
+        uid: &str,
+        filter: &Filter,
+        page: &Page,
+    ) -> Result<Vec<ProjectWithCardinalities>>;
+    /// Returns those projects with any of the given ids, regardless of their members.
+    async fn find_many(&self, ids: &[String]) -> Result<Vec<Project>>;
+    async fn find_by_reference(&self, reference: &str) -> Result<Project>;
+    /// Returns the project with the given id, regardless of its members.
+    async fn find_with_cardinalities(&self, id: &str) -> Result<ProjectWithCardinalities>;
+    /// Returns the project the given user created under the given idempotency key, as long as the key has not
+    /// expired yet, even if the project has been deleted since then.
+    async fn find_by_idempotency_key(&self, key: &str, uid: &str) -> Result<Project>;
+    async fn find_deleted(&self, id: &str, uid: &str) -> Result<Project>;
+    /// Returns all the deleted projects the given user is owner of.
+    async fn find_all_deleted(&self, uid: &str) -> Result<Vec<ProjectWithCardinalities>>;
+    /// Creates the given project along with the given entities as its children, all at once.
+    async fn create(&self, project: &mut Project, entities: &[Entity]) -> Result<()>;
+    /// Reserves the given idempotency key of the given user until the given time. If the key is already
+    /// reserved and has not expired yet, [`Error::AlreadyExists`] is returned.
+    async fn reserve_idempotency_key(
+        &self,
+        key: &str,
+        uid: &str,
+        expires_at: SystemTime,
+    ) -> Result<()>;
+    /// Binds the given idempotency key of the given user, already reserved, to the given project.
+    async fn bind_idempotency_key(&self, key: &str, uid: &str, project: &Project) -> Result<()>;
+    /// Releases the given idempotency key of the given user, so it can be reserved again.
+    async fn release_idempotency_key(&self, key: &str, uid: &str) -> Result<()>;
+    /// Creates the given project as a copy of the source one, including all of its child entities.
+    async fn duplicate(&self, source: &Project, project: &mut Project) -> Result<()>;
+    /// Persists the given project as long as it has not been updated since it was read, increasing its
+    /// version. Otherwise a conflict is returned.
+    async fn update(&self, project: &mut Project) -> Result<()>;
+    /// Same as [`ProjectRepository::update`] but for all the given projects at once. Returns, for each of them
+    /// and in the same order, whether it was persisted or not, since a conflict in any of them does not prevent
+    /// the others from being persisted.
+    async fn update_many(&self, projects: &mut [Project]) -> Result<Vec<Result<()>>>;
+    /// Returns all the child entities of the given project.
+    async fn find_entities(&self, project: &Project) -> Result<Vec<Entity>>;
+    /// Persists the ownership of the given project, moving all of its child entities along with it, as long as
+    /// it has not been updated since it was read. Otherwise a conflict is returned.
+    async fn transfer(&self, project: &mut Project) -> Result<()>;
+    /// Marks the given project as deleted as long as it has not been updated since it was read, increasing its
+    /// version. Otherwise a conflict is returned.
+    async fn delete(&self, project: &mut Project) -> Result<()>;
+    async fn purge(&self, project: &Project) -> Result<()>;
+    async fn purge_deleted_before(&self, deleted_before: SystemTime) -> Result<()>;
 }
+
 #[async_trait::async_trait]
 pub trait EventBus {
     async fn emit_file_created(&self, project: &Project) -> Result<()>;
+    async fn emit_file_updated(&self, project: &Project) -> Result<()>;
+    async fn emit_file_deleted(&self, project: &Project) -> Result<()>;
+    async fn emit_file_transferred(&self, project: &Project) -> Result<()>;
+}
+
+pub trait TokenService {
+    /// Returns the signed representation of the given share token.
+    fn sign(&self, token: &ShareToken) -> Result<String>;
+    /// Returns the share token the given signed representation stands for, if valid.
+    fn verify(&self, token: &str) -> Result<ShareToken>;
+}
+
+/// Determines the order in which projects are listed, right after the highlighted ones.
+#[derive(
+    strum_macros::Display, strum_macros::EnumString, Debug, Default, Clone, Copy, PartialEq, Eq,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum SortBy {
+    #[default]
+    UpdatedAt,
+    CreatedAt,
+    Name,
+}
+
+/// Determines the slice of projects to be retrieved from the repository.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub cursor: Option<Cursor>,
+    /// Maximum amount of projects to retrieve, if any.
+    pub limit: Option<usize>,
+    pub sort_by: SortBy,
+}
+
+/// Points right after the last project of a page, by the very same fields projects are sorted by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub highlight: bool,
+    pub key: SortKey,
+    pub id: String,
+}
+
+impl Cursor {
+    /// Returns the cursor pointing right after the given project for the given order.
+    fn after(project: &Project, sort_by: SortBy) -> Self {
+        let secs = |time: Option<SystemTime>| {
+            time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        };
+
+        let key = match sort_by {
+            SortBy::UpdatedAt => SortKey::Secs(secs(
+                project.meta.updated_at().or(project.meta.created_at()),
+            )),
+            SortBy::CreatedAt => SortKey::Secs(secs(project.meta.created_at())),
+            SortBy::Name => SortKey::Name(project.name.clone()),
+        };
+
+        Self {
+            highlight: project.highlight,
+            key,
+            id: project.id.clone(),
+        }
+    }
+}
+
+/// Value of the field projects are sorted by, as determined by [`SortBy`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SortKey {
+    Secs(u64),
+    Name(String),
+}
+
+/// Content of a page token.
+#[derive(Serialize, Deserialize)]
+struct PageToken {
+    sort_by: String,
+    filter: u64,
+    cursor: Cursor,
+}
+
+/// Determines the conditions a project must satisfy in order to be listed. Unset conditions always hold.
+#[derive(Debug, Default, Clone, Hash)]
+pub struct Filter {
+    pub name: Option<String>,
+    pub highlight: Option<bool>,
+    pub created_after: Option<SystemTime>,
+    pub created_before: Option<SystemTime>,
+    pub updated_after: Option<SystemTime>,
+    pub updated_before: Option<SystemTime>,
+    pub has_reference: Option<bool>,
 }
 
+#[derive(Default, Clone)]
+pub struct ListOptions {
+    /// Maximum amount of projects per page, or the default one if zero. If unset, all of them are listed at
+    /// once.
+    pub page_size: Option<usize>,
+    pub page_token: Option<String>,
+    pub sort_by: SortBy,
+    pub filter: Filter,
+}
+
+/// Determines the fields to be overwritten by an update. Unset fields are left untouched. If any version is
+/// set, the update only applies as long as the project is still at that version.
+#[derive(Default, Clone)]
+pub struct UpdateOptions {
+    pub version: Option<u64>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub fields: Option<BTreeMap<String, String>>,
+}
+
+/// Determines how a project gets created. Creations with an idempotency key already used by the same user
+/// return the project originally created under it instead of a new one, or fail as already existing if that
+/// project is still being created.
 #[derive(Default, Clone)]
 pub struct CreateOptions {
     pub description: String,
     pub reference: Option<String>,
     pub highlight: bool,
+    pub template: Option<Template>,
+    pub idempotency_key: Option<String>,
 }
 
 pub struct ProjectApplication<P: ProjectRepository, B: EventBus> {
     pub project_repo: Arc<P>,
     pub event_bus: Arc<B>,
+    pub changes: broadcast::Sender<ProjectChange>,
+    /// How long an idempotency key is bound to the project created under it.
+    pub idempotency_ttl: Duration,
 }
 
 impl<P: ProjectRepository, B: EventBus> ProjectApplication<P, B> {
-    pub async fn get(&self, id: &str, created_by: &str) -> Result<Project> {
-        info!("processing a \"get\" project request for user {created_by} ",);
-        self.project_repo.find(id, created_by).await
+    pub async fn get(&self, id: &str, uid: &str) -> Result<Project> {
+        info!("processing a \"get\" project request for user {uid} ",);
+        self.find_with_role(id, uid, Role::Viewer).await
     }
 
-    pub async fn list(&self, uid: &str) -> Result<Vec<ProjectWithCardinalities>> {
+    pub async fn list(&self, uid: &str, options: ListOptions) -> Result<ProjectPage> {
         info!("processing a \"list\" projects request for user {} ", uid);
-        self.project_repo.find_all(uid).await
+
+        let page_size = options.page_size.map(|page_size| match page_size {
+            0 => DEFAULT_PAGE_SIZE,
+            page_size => page_size.min(MAX_PAGE_SIZE),
+        });
+
+        let cursor = options
+            .page_token
+            .as_deref()
+            .map(|token| decode_page_token(token, options.sort_by, &options.filter))
+            .transpose()?;
+
+        let page = Page {
+            cursor,
+            limit: page_size.map(|page_size| page_size + 1),
+            sort_by: options.sort_by,
+        };
+
+        let mut projects = self
+            .project_repo
+            .find_all(uid, &options.filter, &page)
+            .await?;
+        let next_page_token = match page_size {
+            Some(page_size) if projects.len() > page_size => {
+                projects.truncate(page_size);
+                projects.last().map(|last| {
+                    encode_page_token(
+                        options.sort_by,
+                        &options.filter,
+                        Cursor::after(&last.project, options.sort_by),
+                    )
+                })
+            }
+            _ => None,
+        };
+
+        Ok(ProjectPage {
+            projects,
+            next_page_token,
+        })
     }
 
     pub async fn create(
@@ -47,6 +274,40 @@ impl<P: ProjectRepository, B: EventBus> ProjectApplication<P, B> {
     ) -> Result<Project> {
         info!("processing a \"create\" project request for user {created_by}",);
 
+        if let Some(key) = &options.idempotency_key {
+            if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+                return Err(Error::InvalidFormat("idempotency-key"));
+            }
+
+            let expires_at = SystemTime::now() + self.idempotency_ttl;
+            match self
+                .project_repo
+                .reserve_idempotency_key(key, created_by, expires_at)
+                .await
+            {
+                Ok(()) => {}
+                Err(Error::AlreadyExists) => {
+                    // the key may be reserved by a request that has not created its project yet
+                    let project = self
+                        .project_repo
+                        .find_by_idempotency_key(key, created_by)
+                        .await
+                        .map_err(|err| match err {
+                            Error::NotFound => Error::AlreadyExists,
+                            err => err,
+                        })?;
+
+                    info!(
+                        "project {} was already created under idempotency key {}",
+                        project.id, key
+                    );
+
+                    return Ok(project);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
         let meta = Metadata::new(created_by);
         let mut project = Project {
             id: "".to_string(),
@@ -54,28 +315,803 @@ impl<P: ProjectRepository, B: EventBus> ProjectApplication<P, B> {
             description: options.description,
             reference: options.reference,
             highlight: options.highlight,
+            fields: options
+                .template
+                .as_ref()
+                .map(|template| template.fields().clone())
+                .unwrap_or_default(),
+            members: Vec::new(),
+            share_tokens: Vec::new(),
+            version: 1,
             meta,
         };
 
-        self.project_repo.create(&mut project).await?;
+        let entities = options
+            .template
+            .as_ref()
+            .map(|template| template.entities())
+            .unwrap_or_default();
+
+        if let Err(err) = self.project_repo.create(&mut project, entities).await {
+            if let Some(key) = &options.idempotency_key {
+                if let Err(err) = self
+                    .project_repo
+                    .release_idempotency_key(key, created_by)
+                    .await
+                {
+                    warn!("{} releasing idempotency key {}", err, key);
+                }
+            }
+
+            return Err(err);
+        }
+
+        if let Some(key) = &options.idempotency_key {
+            if let Err(err) = self
+                .project_repo
+                .bind_idempotency_key(key, created_by, &project)
+                .await
+            {
+                // the project already exists, so failing here would only make the client retry it, while the
+                // key remains reserved anyway
+                warn!(
+                    "{} binding idempotency key {} to project {}",
+                    err, key, project.id
+                );
+            }
+        }
+
+        self.event_bus.emit_file_created(&project).await?;
+        self.notify(ChangeKind::Created, &project);
+        Ok(project)
+    }
+
+    pub async fn duplicate(&self, id: &str, uid: &str) -> Result<Project> {
+        info!("processing a \"duplicate\" project request for user {uid}",);
+
+        let source = self.find_with_role(id, uid, Role::Viewer).await?;
+        let mut project = Project {
+            id: "".to_string(),
+            name: format!("{} (copy)", source.name),
+            description: source.description.clone(),
+            reference: None,
+            highlight: false,
+            fields: source.fields.clone(),
+            members: Vec::new(),
+            share_tokens: Vec::new(),
+            version: 1,
+            meta: Metadata::new(uid),
+        };
+
+        self.project_repo.duplicate(&source, &mut project).await?;
         self.event_bus.emit_file_created(&project).await?;
+        self.notify(ChangeKind::Created, &project);
         Ok(project)
     }
 
-    pub async fn update(
+    pub async fn update(&self, id: &str, uid: &str, options: UpdateOptions) -> Result<Project> {
+        info!("processing a \"update\" project request for user {uid}",);
+
+        if options.name.as_deref() == Some("") {
+            return Err(Error::MissingFields("name"));
+        }
+
+        let mut project = self.find_with_role(id, uid, Role::Editor).await?;
+        check_version(&project, options.version)?;
+        apply_update(&mut project, options);
+
+        self.project_repo.update(&mut project).await?;
+        self.event_bus.emit_file_updated(&project).await?;
+        self.notify(ChangeKind::Updated, &project);
+        Ok(project)
+    }
+
+    /// Returns, for each of the given ids and in the same order, either the project with that id or the reason
+    /// why it could not be retrieved.
+    pub async fn batch_get(
         &self,
-        id: &str,
-        name: &str,
-        description: &str,
-        created_by: &str,
-    ) -> Result<Project> {
-        info!("processing a \"update\" project request for user {created_by}",);
+        ids: &[String],
+        uid: &str,
+    ) -> Result<Vec<(String, Result<Project>)>> {
+        info!("processing a \"batch get\" projects request for user {uid}");
+
+        if ids.len() > MAX_BATCH_SIZE {
+            return Err(Error::InvalidFormat("ids"));
+        }
+
+        let projects = self.project_repo.find_many(ids).await?;
+        Ok(ids
+            .iter()
+            .map(|id| {
+                let result = match projects.iter().find(|project| &project.id == id) {
+                    Some(project) => authorize(project, uid, Role::Viewer).map(|_| project.clone()),
+                    None => Err(Error::NotFound),
+                };
+
+                (id.clone(), result)
+            })
+            .collect())
+    }
+
+    /// Applies all the given updates the given user is allowed to at once. Returns, for each of them and in the
+    /// same order, either the updated project or the reason why it could not be updated.
+    pub async fn batch_update(
+        &self,
+        updates: Vec<(String, UpdateOptions)>,
+        uid: &str,
+    ) -> Result<Vec<(String, Result<Project>)>> {
+        info!("processing a \"batch update\" projects request for user {uid}");
+
+        if updates.len() > MAX_BATCH_SIZE {
+            return Err(Error::InvalidFormat("requests"));
+        }
+
+        // a project updated twice would be persisted with whichever update is applied last only
+        let mut seen = HashSet::with_capacity(updates.len());
+        if !updates.iter().all(|(id, _)| seen.insert(id)) {
+            warn!(
+                "{} batch update targets the same project more than once",
+                Error::InvalidFormat("requests")
+            );
+            return Err(Error::InvalidFormat("requests"));
+        }
+
+        let ids: Vec<String> = updates.iter().map(|(id, _)| id.clone()).collect();
+        let mut projects = self.project_repo.find_many(&ids).await?;
+
+        // updated projects are in the same order as the successful results they belong to
+        let mut results = Vec::with_capacity(updates.len());
+        let mut updated = Vec::new();
+        for (id, options) in updates {
+            let project = projects.iter_mut().find(|project| project.id == id);
+            let result = match project {
+                _ if options.name.as_deref() == Some("") => Err(Error::MissingFields("name")),
+                None => Err(Error::NotFound),
+                Some(project) => authorize(project, uid, Role::Editor)
+                    .and_then(|_| check_version(project, options.version))
+                    .map(|_| {
+                        apply_update(project, options);
+                        updated.push(project.clone());
+                    }),
+            };
+
+            results.push((id, result));
+        }
+
+        let outcomes = self.project_repo.update_many(&mut updated).await?;
+        let mut persisted = Vec::with_capacity(updated.len());
+        for (project, outcome) in updated.into_iter().zip(outcomes) {
+            if outcome.is_ok() {
+                self.event_bus.emit_file_updated(&project).await?;
+                self.notify(ChangeKind::Updated, &project);
+            }
+
+            persisted.push(outcome.map(|_| project));
+        }
+
+        let mut persisted = persisted.into_iter();
+        Ok(results
+            .into_iter()
+            .map(|(id, result)| {
+                let result = result.and_then(|_| persisted.next().unwrap_or(Err(Error::Unknown)));
+                (id, result)
+            })
+            .collect())
+    }
+
+    pub async fn pin(&self, id: &str, uid: &str) -> Result<Project> {
+        info!("processing a \"pin\" project request for user {uid}",);
+        self.set_highlight(id, uid, true).await
+    }
+
+    pub async fn unpin(&self, id: &str, uid: &str) -> Result<Project> {
+        info!("processing an \"unpin\" project request for user {uid}",);
+        self.set_highlight(id, uid, false).await
+    }
+
+    async fn set_highlight(&self, id: &str, uid: &str, highlight: bool) -> Result<Project> {
+        let mut project = self.find_with_role(id, uid, Role::Editor).await?;
+        if project.highlight == highlight {
+            return Ok(project);
+        }
+
+        project.highlight = highlight;
+        project.meta.touch();
+
+        self.project_repo.update(&mut project).await?;
+        self.notify(ChangeKind::Updated, &project);
+        Ok(project)
+    }
+
+    pub async fn transfer(&self, id: &str, owner: &str, uid: &str) -> Result<Project> {
+        info!("processing a \"transfer ownership\" project request for user {uid}",);
+
+        if owner.is_empty() {
+            return Err(Error::MissingFields("user_id"));
+        }
+
+        let mut project = self.find_with_role(id, uid, Role::Owner).await?;
+        if project.meta.created_by() == Some(owner) {
+            return Ok(project);
+        }
+
+        project.members.retain(|member| member.user_id != owner);
+        project.meta.transfer(owner);
+
+        self.project_repo.transfer(&mut project).await?;
+        self.event_bus.emit_file_transferred(&project).await?;
+        self.notify_revoked(
+            &project,
+            project
+                .meta
+                .previous_owner()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        );
+        Ok(project)
+    }
+
+    pub async fn share(&self, id: &str, user_id: &str, role: Role, uid: &str) -> Result<Project> {
+        info!("processing a \"share\" project request for user {uid}",);
+
+        if user_id.is_empty() {
+            return Err(Error::MissingFields("user_id"));
+        }
+
+        let mut project = self.find_with_role(id, uid, Role::Owner).await?;
+        if project.meta.created_by() == Some(user_id) {
+            return Err(Error::AlreadyExists);
+        }
+
+        match project
+            .members
+            .iter_mut()
+            .find(|member| member.user_id == user_id)
+        {
+            Some(member) => member.role = role,
+            None => project.members.push(Member {
+                user_id: user_id.to_string(),
+                role,
+            }),
+        }
+
+        project.meta.touch();
+
+        self.project_repo.update(&mut project).await?;
+        self.notify(ChangeKind::Updated, &project);
+        Ok(project)
+    }
+
+    pub async fn unshare(&self, id: &str, user_id: &str, uid: &str) -> Result<Project> {
+        info!("processing an \"unshare\" project request for user {uid}",);
+
+        let mut project = self.find_with_role(id, uid, Role::Owner).await?;
+        let total_members = project.members.len();
+        project.members.retain(|member| member.user_id != user_id);
+
+        if project.members.len() == total_members {
+            return Err(Error::NotFound);
+        }
+
+        project.meta.touch();
+
+        self.project_repo.update(&mut project).await?;
+        self.notify_revoked(&project, vec![user_id.to_string()]);
+        Ok(project)
+    }
+
+    pub async fn delete(&self, id: &str, uid: &str) -> Result<()> {
+        info!("processing a \"delete\" project request for user {uid}",);
+
+        let mut project = self.find_with_role(id, uid, Role::Owner).await?;
+        project.meta.delete();
+
+        self.project_repo.delete(&mut project).await?;
+        self.event_bus.emit_file_deleted(&project).await?;
+        self.notify(ChangeKind::Deleted, &project);
+        Ok(())
+    }
+
+    /// Deletes the project referencing the given file. No file event is emitted, since this is expected to be
+    /// called once the referenced file has already been deleted.
+    pub async fn delete_by_reference(&self, reference: &str) -> Result<()> {
+        info!("processing a \"delete\" project request for reference {reference}");
+
+        let mut project = self.project_repo.find_by_reference(reference).await?;
+        project.meta.delete();
+
+        self.project_repo.delete(&mut project).await?;
+        self.notify(ChangeKind::Deleted, &project);
+        Ok(())
+    }
+
+    pub async fn list_deleted(&self, uid: &str) -> Result<Vec<ProjectWithCardinalities>> {
+        info!("processing a \"list deleted\" projects request for user {uid}");
+        self.project_repo.find_all_deleted(uid).await
+    }
+
+    pub async fn restore(&self, id: &str, uid: &str) -> Result<Project> {
+        info!("processing a \"restore\" project request for user {uid}",);
 
-        let mut project = self.project_repo.find(id, created_by).await?;
-        project.description = description.to_string();
-        project.name = name.to_string();
+        let mut project = self.find_deleted_with_role(id, uid, Role::Owner).await?;
+        project.meta.restore();
+        project.meta.touch();
 
-        self.project_repo.update(&project).await?;
+        self.project_repo.update(&mut project).await?;
+        // the backing file got removed once deleted, so it has to be created all over again
+        self.event_bus.emit_file_created(&project).await?;
+        self.notify(ChangeKind::Created, &project);
         Ok(project)
     }
+
+    pub async fn purge(&self, id: &str, uid: &str) -> Result<()> {
+        info!("processing a \"purge\" project request for user {uid}",);
+
+        let project = self.find_deleted_with_role(id, uid, Role::Owner).await?;
+        self.project_repo.purge(&project).await
+    }
+
+    /// Removes for good all those projects that have been deleted for longer than the given retention.
+    pub async fn purge_expired(&self, retention: Duration) -> Result<()> {
+        let deleted_before = SystemTime::now()
+            .checked_sub(retention)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        info!(
+            "purging projects deleted more than {} seconds ago",
+            retention.as_secs()
+        );
+
+        self.project_repo.purge_deleted_before(deleted_before).await
+    }
+
+    /// Returns a never ending stream of the changes performed from now on over any project the given user is
+    /// member of. Those users losing any access to a project get it as deleted instead.
+    ///
+    /// Changes are only delivered to the watchers of the very same process performing them, hence those
+    /// performed by the agent are never watched.
+    pub fn watch(&self, uid: &str) -> impl Stream<Item = ProjectChange> + Send + 'static {
+        info!("processing a \"watch\" projects request for user {uid}");
+
+        let uid = uid.to_string();
+        stream::unfold(self.changes.subscribe(), move |mut receiver| {
+            let uid = uid.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(change) if change.project.role_of(&uid).is_some() => {
+                            return Some((change, receiver))
+                        }
+                        Ok(change) if change.revoked.contains(&uid) => {
+                            let change = ProjectChange {
+                                kind: ChangeKind::Deleted,
+                                ..change
+                            };
+
+                            return Some((change, receiver));
+                        }
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("watcher of user {uid} lagged behind, {skipped} changes skipped");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+    }
+
+    /// Persists the given project as updated right now, as it is whenever any of its child entities changes.
+    pub(crate) async fn touch(&self, project: &mut Project) -> Result<()> {
+        project.meta.touch();
+
+        self.project_repo.update(project).await?;
+        self.event_bus.emit_file_updated(project).await?;
+        self.notify(ChangeKind::Updated, project);
+        Ok(())
+    }
+
+    /// Notifies the given change to all the watchers, if any.
+    fn notify(&self, kind: ChangeKind, project: &Project) {
+        // sending only fails when there is no watcher at all
+        let _ = self.changes.send(ProjectChange {
+            kind,
+            project: project.clone(),
+            revoked: Vec::new(),
+        });
+    }
+
+    /// Notifies the given project as updated to all the watchers, if any, and as deleted to those of the
+    /// given users, which just lost any access to it.
+    fn notify_revoked(&self, project: &Project, revoked: Vec<String>) {
+        // sending only fails when there is no watcher at all
+        let _ = self.changes.send(ProjectChange {
+            kind: ChangeKind::Updated,
+            project: project.clone(),
+            revoked,
+        });
+    }
+
+    /// Returns the project with the given id if, and only if, the given user has at least the given role
+    /// over it.
+    pub(crate) async fn find_with_role(&self, id: &str, uid: &str, role: Role) -> Result<Project> {
+        let project = self.project_repo.find(id, uid).await?;
+        authorize(&project, uid, role)?;
+        Ok(project)
+    }
+
+    /// Same as [`ProjectApplication::find_with_role`] but for deleted projects.
+    async fn find_deleted_with_role(&self, id: &str, uid: &str, role: Role) -> Result<Project> {
+        let project = self.project_repo.find_deleted(id, uid).await?;
+        authorize(&project, uid, role)?;
+        Ok(project)
+    }
+}
+
+pub struct ShareApplication<P: ProjectRepository, T: TokenService> {
+    pub project_repo: Arc<P>,
+    pub token_service: Arc<T>,
+}
+
+impl<P: ProjectRepository, T: TokenService> ShareApplication<P, T> {
+    /// Issues a new share token for the project with the given id, returning it along with its signed
+    /// representation.
+    pub async fn create(
+        &self,
+        id: &str,
+        ttl: Option<Duration>,
+        uid: &str,
+    ) -> Result<(ShareToken, String)> {
+        info!("processing a \"create share token\" request for user {uid}",);
+
+        let mut project = self.project_repo.find(id, uid).await?;
+        authorize(&project, uid, Role::Owner)?;
+
+        let token = ShareToken {
+            id: Uuid::new_v4().simple().to_string(),
+            project_id: project.id.clone(),
+            expires_at: ttl.map(|ttl| SystemTime::now() + ttl),
+        };
+
+        let signed = self.token_service.sign(&token)?;
+
+        project.share_tokens.retain(|token| !token.is_expired());
+        project.share_tokens.push(token.clone());
+        project.meta.touch();
+
+        self.project_repo.update(&mut project).await?;
+        Ok((token, signed))
+    }
+
+    pub async fn revoke(&self, id: &str, token_id: &str, uid: &str) -> Result<()> {
+        info!("processing a \"revoke share token\" request for user {uid}",);
+
+        let mut project = self.project_repo.find(id, uid).await?;
+        authorize(&project, uid, Role::Owner)?;
+
+        let total_tokens = project.share_tokens.len();
+        project.share_tokens.retain(|token| token.id != token_id);
+
+        if project.share_tokens.len() == total_tokens {
+            return Err(Error::NotFound);
+        }
+
+        project.meta.touch();
+        self.project_repo.update(&mut project).await
+    }
+
+    /// Returns the project the given signed share token grants access to, as long as the token has been
+    /// neither revoked nor expired.
+    pub async fn get(&self, token: &str) -> Result<ProjectWithCardinalities> {
+        let mut shared = self.find_shared(token, "get shared project").await?;
+        shared.project.members.clear();
+        shared.project.share_tokens.clear();
+        Ok(shared)
+    }
+
+    /// Returns all the child entities of the project the given signed share token grants access to, as long as
+    /// the token has been neither revoked nor expired.
+    pub async fn list_entities(&self, token: &str) -> Result<Vec<Entity>> {
+        let shared = self.find_shared(token, "list shared entities").await?;
+        self.project_repo.find_entities(&shared.project).await
+    }
+
+    /// Returns the project the given signed share token grants access to, as long as the token has been
+    /// neither revoked nor expired. The request is logged as the given one.
+    async fn find_shared(&self, token: &str, request: &str) -> Result<ProjectWithCardinalities> {
+        let claims = self.token_service.verify(token)?;
+        info!(
+            "processing a \"{}\" request for token {}",
+            request, claims.id
+        );
+
+        let shared = match self
+            .project_repo
+            .find_with_cardinalities(&claims.project_id)
+            .await
+        {
+            Err(Error::NotFound) => return Err(Error::InvalidToken),
+            other => other?,
+        };
+
+        if !shared
+            .project
+            .share_tokens
+            .iter()
+            .any(|token| token.id == claims.id && !token.is_expired())
+        {
+            warn!(
+                "{} share token {} has been revoked or is expired",
+                Error::InvalidToken,
+                claims.id
+            );
+
+            return Err(Error::InvalidToken);
+        }
+
+        Ok(shared)
+    }
+}
+
+/// Returns a conflict if the given project is not at the expected version, if any.
+fn check_version(project: &Project, version: Option<u64>) -> Result<()> {
+    match version {
+        Some(version) if version != project.version => {
+            warn!(
+                "{} project {} is at version {} but {} was expected",
+                Error::Conflict,
+                project.id,
+                project.version,
+                version
+            );
+
+            Err(Error::Conflict)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Overwrites the fields of the given project as determined by the given options.
+fn apply_update(project: &mut Project, options: UpdateOptions) {
+    if let Some(name) = options.name {
+        project.name = name;
+    }
+
+    if let Some(description) = options.description {
+        project.description = description;
+    }
+
+    if let Some(fields) = options.fields {
+        project.fields = fields;
+    }
+
+    project.meta.touch();
+}
+
+/// Returns an opaque token pointing to the page right after the given cursor for the given order and filter.
+fn encode_page_token(sort_by: SortBy, filter: &Filter, cursor: Cursor) -> String {
+    let token = PageToken {
+        sort_by: sort_by.to_string(),
+        filter: filter_hash(filter),
+        cursor,
+    };
+
+    serde_json::to_vec(&token)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Returns the cursor the given page token points to, as long as it was issued for the same order and filter.
+fn decode_page_token(token: &str, sort_by: SortBy, filter: &Filter) -> Result<Cursor> {
+    let decoded = (0..token.len())
+        .step_by(2)
+        .map(|index| {
+            token
+                .get(index..index + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect::<Option<Vec<u8>>>();
+
+    let Some(decoded) = decoded.and_then(|bytes| serde_json::from_slice::<PageToken>(&bytes).ok())
+    else {
+        warn!(
+            "{} decoding page token {}",
+            Error::InvalidFormat("page_token"),
+            token
+        );
+        return Err(Error::InvalidFormat("page_token"));
+    };
+
+    if decoded.sort_by != sort_by.to_string() {
+        warn!(
+            "{} page token issued for order {} but got {}",
+            Error::InvalidFormat("page_token"),
+            decoded.sort_by,
+            sort_by
+        );
+
+        return Err(Error::InvalidFormat("page_token"));
+    }
+
+    if decoded.filter != filter_hash(filter) {
+        warn!(
+            "{} page token issued for a different filter than {:?}",
+            Error::InvalidFormat("page_token"),
+            filter
+        );
+
+        return Err(Error::InvalidFormat("page_token"));
+    }
+
+    Ok(decoded.cursor)
+}
+
+/// Returns the fingerprint of the given filter, binding page tokens to the filter they were issued for.
+fn filter_hash(filter: &Filter) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    filter.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns an error if the given user has no role over the given project, or it is less privileged than the
+/// required one.
+fn authorize(project: &Project, uid: &str, role: Role) -> Result<()> {
+    if project
+        .role_of(uid)
+        .filter(|granted| granted >= &role)
+        .is_none()
+    {
+        warn!(
+            "{} user {} has no {} role over project {}",
+            Error::Unauthorized,
+            uid,
+            role,
+            project.id
+        );
+
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::mock;
+
+    fn cursor() -> Cursor {
+        Cursor {
+            highlight: true,
+            key: SortKey::Name("Middle-earth: the \"fellowship\"".to_string()),
+            id: "project:abc".to_string(),
+        }
+    }
+
+    fn filter() -> Filter {
+        Filter {
+            name: Some("middle".to_string()),
+            has_reference: Some(false),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn page_tokens_point_to_the_cursor_they_were_issued_for() {
+        let token = encode_page_token(SortBy::Name, &filter(), cursor());
+        assert_eq!(
+            decode_page_token(&token, SortBy::Name, &filter()),
+            Ok(cursor())
+        );
+    }
+
+    #[test]
+    fn tampered_page_tokens_are_rejected() {
+        let token = encode_page_token(SortBy::Name, &filter(), cursor());
+        let mut truncated = token.clone();
+        truncated.truncate(token.len() - 2);
+
+        for tampered in [
+            truncated,
+            format!("{token}0"),
+            token.replacen(&token[..2], "zz", 1),
+            "not a token".to_string(),
+            "".to_string(),
+        ] {
+            assert_eq!(
+                decode_page_token(&tampered, SortBy::Name, &filter()),
+                Err(Error::InvalidFormat("page_token")),
+                "token {tampered} was accepted"
+            );
+        }
+    }
+
+    #[test]
+    fn page_tokens_issued_for_another_order_are_rejected() {
+        let token = encode_page_token(SortBy::Name, &filter(), cursor());
+        assert_eq!(
+            decode_page_token(&token, SortBy::CreatedAt, &filter()),
+            Err(Error::InvalidFormat("page_token"))
+        );
+    }
+
+    #[test]
+    fn page_tokens_issued_for_another_filter_are_rejected() {
+        let token = encode_page_token(SortBy::Name, &filter(), cursor());
+        let other = Filter {
+            has_reference: Some(true),
+            ..filter()
+        };
+
+        assert_eq!(
+            decode_page_token(&token, SortBy::Name, &other),
+            Err(Error::InvalidFormat("page_token"))
+        );
+
+        assert_eq!(
+            decode_page_token(&token, SortBy::Name, &Filter::default()),
+            Err(Error::InvalidFormat("page_token"))
+        );
+    }
+
+    #[test]
+    fn equal_filters_share_their_hash() {
+        assert_eq!(filter_hash(&filter()), filter_hash(&filter()));
+        assert_ne!(filter_hash(&filter()), filter_hash(&Filter::default()));
+    }
+
+    #[tokio::test]
+    async fn pages_list_every_project_once_and_in_order() {
+        let project_app = mock::project_application(Default::default(), Default::default());
+        for name in ["e", "b", "d", "a", "c"] {
+            project_app
+                .create(name, "owner", CreateOptions::default())
+                .await
+                .unwrap();
+        }
+
+        let mut names = Vec::new();
+        let mut page_token = None;
+        loop {
+            let options = ListOptions {
+                page_size: Some(2),
+                page_token,
+                sort_by: SortBy::Name,
+                ..Default::default()
+            };
+
+            let page = project_app.list("owner", options).await.unwrap();
+            assert!(page.projects.len() <= 2);
+            names.extend(page.projects.into_iter().map(|item| item.project.name));
+
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(names, ["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn batch_updates_targeting_a_project_twice_are_rejected() {
+        let project_app = mock::project_application(Default::default(), Default::default());
+        let project = project_app
+            .create("project", "owner", CreateOptions::default())
+            .await
+            .unwrap();
+
+        let updates = vec![
+            (project.id.clone(), UpdateOptions::default()),
+            (project.id.clone(), UpdateOptions::default()),
+        ];
+
+        assert!(matches!(
+            project_app.batch_update(updates, "owner").await,
+            Err(Error::InvalidFormat("requests"))
+        ));
+    }
 }
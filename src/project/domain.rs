@@ -1,15 +1,85 @@
 //! Domain layer of the project entity.
 
 use crate::metadata::domain::Metadata;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// Represents the level of access a user has over a project, sorted from the least to the most privileged
+#[derive(
+    strum_macros::Display,
+    strum_macros::EnumString,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Editor,
+    Owner,
+}
+
+/// Represents a user having access to a project other than its owner
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub(super) user_id: String,
+    pub(super) role: Role,
+}
+
+impl Member {
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+}
+
+/// Represents a link granting anonymous read-only access to a project
+#[derive(Debug, Clone)]
+pub struct ShareToken {
+    pub(super) id: String,
+    pub(super) project_id: String,
+    pub(super) expires_at: Option<SystemTime>,
+}
+
+impl ShareToken {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn project_id(&self) -> &str {
+        &self.project_id
+    }
+
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        self.expires_at
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at <= SystemTime::now())
+            .unwrap_or_default()
+    }
+}
 
 /// Represents a project
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Project {
     pub(super) id: String,
     pub(super) name: String,
     pub(super) description: String,
     pub(super) reference: Option<String>,
     pub(super) highlight: bool,
+    pub(super) fields: BTreeMap<String, String>,
+    pub(super) members: Vec<Member>,
+    pub(super) share_tokens: Vec<ShareToken>,
+    pub(super) version: u64,
     pub(super) meta: Metadata,
 }
 
@@ -30,9 +100,43 @@ impl Project {
         self.reference.as_deref()
     }
 
+    pub fn highlight(&self) -> bool {
+        self.highlight
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    pub fn share_tokens(&self) -> &[ShareToken] {
+        &self.share_tokens
+    }
+
+    /// Returns the amount of times the project has been persisted since its creation, included.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     pub fn meta(&self) -> &Metadata {
         &self.meta
     }
+
+    /// Returns the role the given user has over the project, if any. The user in the metadata is always
+    /// considered its owner.
+    pub fn role_of(&self, uid: &str) -> Option<Role> {
+        if self.meta.created_by() == Some(uid) {
+            return Some(Role::Owner);
+        }
+
+        self.members
+            .iter()
+            .find(|member| member.user_id == uid)
+            .map(|member| member.role)
+    }
 }
 
 /// Represents all the cardinalities a project could have
@@ -50,3 +154,28 @@ pub struct ProjectWithCardinalities {
     pub project: Project,
     pub cardinalities: Cardinalities,
 }
+
+/// Represents a slice of the projects a user has access to
+#[derive(Debug)]
+pub struct ProjectPage {
+    pub projects: Vec<ProjectWithCardinalities>,
+    pub next_page_token: Option<String>,
+}
+
+/// Represents all the kinds of change a project may go through, as seen by the users listing it
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Represents the state of a project right after a change
+#[derive(Debug, Clone)]
+pub struct ProjectChange {
+    pub kind: ChangeKind,
+    pub project: Project,
+    /// Users that lost any access to the project because of this change.
+    pub(super) revoked: Vec<String>,
+}
@@ -5,8 +5,172 @@ use crate::project::{
     application::{ProjectApplication, ProjectRepository},
     domain,
 };
+use crate::result::Error;
+use crate::template::{
+    application::{TemplateApplication, TemplateRepository},
+    domain as template_domain,
+};
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::time::{Duration, UNIX_EPOCH};
 use tonic::{Request, Response, Status};
 
+/// Implements the conversions between the domain and those proto messages that are alike in every version of
+/// the service, given the messages listing templates and template entities respectively. Must be invoked from
+/// within the module importing the proto messages of the version.
+macro_rules! impl_common_conversions {
+    ($template_list:ident, $entity_list:ident) => {
+        impl From<domain::Member> for Member {
+            fn from(value: domain::Member) -> Self {
+                Self {
+                    user_id: value.user_id,
+                    role: Role::from(value.role) as i32,
+                }
+            }
+        }
+
+        impl From<domain::Role> for Role {
+            fn from(value: domain::Role) -> Self {
+                match value {
+                    domain::Role::Viewer => Role::Viewer,
+                    domain::Role::Editor => Role::Editor,
+                    domain::Role::Owner => Role::Owner,
+                }
+            }
+        }
+
+        impl From<Role> for Option<domain::Role> {
+            fn from(value: Role) -> Self {
+                match value {
+                    Role::Unspecified => None,
+                    Role::Viewer => Some(domain::Role::Viewer),
+                    Role::Editor => Some(domain::Role::Editor),
+                    Role::Owner => Some(domain::Role::Owner),
+                }
+            }
+        }
+
+        impl From<SortField> for SortBy {
+            fn from(value: SortField) -> Self {
+                match value {
+                    SortField::UpdatedAt => SortBy::UpdatedAt,
+                    SortField::CreatedAt => SortBy::CreatedAt,
+                    SortField::Name => SortBy::Name,
+                }
+            }
+        }
+
+        impl From<domain::ChangeKind> for ChangeKind {
+            fn from(value: domain::ChangeKind) -> Self {
+                match value {
+                    domain::ChangeKind::Created => ChangeKind::Created,
+                    domain::ChangeKind::Updated => ChangeKind::Updated,
+                    domain::ChangeKind::Deleted => ChangeKind::Deleted,
+                }
+            }
+        }
+
+        impl From<domain::ProjectChange> for ProjectChange {
+            fn from(value: domain::ProjectChange) -> Self {
+                Self {
+                    kind: ChangeKind::from(value.kind) as i32,
+                    project: Some(value.project.into()),
+                }
+            }
+        }
+
+        impl From<template_domain::Template> for Template {
+            fn from(value: template_domain::Template) -> Self {
+                Self {
+                    builtin: value.is_builtin(),
+                    id: value.id().to_string(),
+                    name: value.name().to_string(),
+                    description: value.description().to_string(),
+                    fields: value.fields().clone().into_iter().collect(),
+                    entities: value.entities().iter().map(Into::into).collect(),
+                }
+            }
+        }
+
+        impl From<&template_domain::Entity> for TemplateEntity {
+            fn from(value: &template_domain::Entity) -> Self {
+                Self {
+                    kind: value.kind().to_string(),
+                    name: value.name().to_string(),
+                    description: value.description().to_string(),
+                }
+            }
+        }
+
+        impl From<Vec<template_domain::Template>> for $template_list {
+            fn from(value: Vec<template_domain::Template>) -> Self {
+                Self {
+                    templates: value.into_iter().map(Into::into).collect(),
+                }
+            }
+        }
+
+        impl From<Vec<template_domain::Entity>> for $entity_list {
+            fn from(value: Vec<template_domain::Entity>) -> Self {
+                Self {
+                    entities: value.iter().map(Into::into).collect(),
+                }
+            }
+        }
+
+        impl From<Vec<(String, crate::result::Result<domain::Project>)>> for BatchProjectsResponse {
+            fn from(value: Vec<(String, crate::result::Result<domain::Project>)>) -> Self {
+                Self {
+                    results: value
+                        .into_iter()
+                        .map(|(id, result)| BatchProjectResult {
+                            id,
+                            result: Some(match result {
+                                Ok(project) => {
+                                    batch_project_result::Result::Project(project.into())
+                                }
+                                Err(err) => batch_project_result::Result::Error(err.into()),
+                            }),
+                        })
+                        .collect(),
+                }
+            }
+        }
+
+        impl From<Project> for $crate::project::grpc::ProjectParts {
+            fn from(value: Project) -> Self {
+                Self {
+                    id: value.id,
+                    name: value.name,
+                    description: value.description,
+                    fields: value.fields,
+                    version: value.version,
+                }
+            }
+        }
+
+        /// Returns the parts of the project to be updated along with the paths of the given request's update
+        /// mask.
+        fn masked_parts(
+            request: UpdateProjectRequest,
+        ) -> Result<($crate::project::grpc::ProjectParts, Vec<String>), Status> {
+            let Some(project) = request.project else {
+                return Err(Error::MissingFields("project").into());
+            };
+
+            let paths = request
+                .update_mask
+                .map(|update_mask| update_mask.paths)
+                .unwrap_or_default();
+
+            Ok((project.into(), paths))
+        }
+    };
+}
+
+pub mod v1;
+
 // Import the generated rust code into module
 mod proto {
     tonic::include_proto!("project");
@@ -17,22 +181,147 @@ use proto::project_service_server::ProjectService;
 pub use proto::project_service_server::ProjectServiceServer;
 
 // Proto message structs
-use proto::{Empty, Project, ProjectList, ProjectWithCardinalities};
+use proto::{
+    batch_project_result, BatchGetProjectsRequest, BatchProjectResult, BatchProjectsResponse,
+    BatchUpdateProjectsRequest, ChangeKind, CreateShareTokenRequest, Empty, ListProjectsRequest,
+    Member, Project, ProjectChange, ProjectFilter, ProjectList, ProjectWithCardinalities,
+    RevokeShareTokenRequest, Role, SaveTemplateRequest, ShareRequest, ShareToken, SharedEntityList,
+    SharedProjectRequest, SortField, Template, TemplateEntity, TemplateList,
+    TransferOwnershipRequest, UpdateProjectRequest,
+};
 
 use self::proto::Cardinality;
-use super::application::{CreateOptions, EventBus};
+use super::application::{
+    CreateOptions, EventBus, Filter, ListOptions, ShareApplication, SortBy, TokenService,
+    UpdateOptions,
+};
 
-pub struct GrpcProjectServer<P: ProjectRepository + Sync + Send, B: EventBus + Sync + Send> {
+/// Paths of a project an update mask may contain. An empty mask stands for all of them in the versioned
+/// service.
+const UPDATABLE_PATHS: [&str; 3] = ["name", "description", "fields"];
+
+/// Paths an empty update mask stands for in the unversioned service, whose clients may know nothing about
+/// custom fields and must not wipe them out.
+const LEGACY_UPDATABLE_PATHS: [&str; 2] = ["name", "description"];
+
+/// Serves the project's application through every version of the gRPC service.
+pub struct GrpcProjectServer<
+    P: ProjectRepository + Sync + Send,
+    B: EventBus + Sync + Send,
+    T: TokenService + Sync + Send,
+    R: TemplateRepository + Sync + Send,
+> {
     pub project_app: ProjectApplication<P, B>,
-    pub uid_header: &'static str,
+    pub share_app: ShareApplication<P, T>,
+    pub template_app: TemplateApplication<R, P>,
+}
+
+/// Parts of a project an update may overwrite, as sent through any version of the service.
+struct ProjectParts {
+    id: String,
+    name: String,
+    description: String,
+    fields: HashMap<String, String>,
+    version: u64,
+}
+
+/// Handlers shared by every version of the service, taking the already decoded content of the request.
+impl<
+        P: 'static + ProjectRepository + Sync + Send,
+        B: 'static + EventBus + Sync + Send,
+        T: 'static + TokenService + Sync + Send,
+        R: 'static + TemplateRepository + Sync + Send,
+    > GrpcProjectServer<P, B, T, R>
+{
+    /// Creates a project out of the template with the given id, if any.
+    async fn create_from_template(
+        &self,
+        name: &str,
+        template_id: &str,
+        uid: &str,
+        options: CreateOptions,
+    ) -> Result<domain::Project, Status> {
+        let template = match template_id {
+            "" => None,
+            template_id => Some(self.template_app.get(template_id, uid).await?),
+        };
+
+        self.project_app
+            .create(
+                name,
+                uid,
+                CreateOptions {
+                    template,
+                    ..options
+                },
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Updates those paths of the given parts' project included in the given mask, or the default ones if
+    /// empty.
+    async fn update_with_mask(
+        &self,
+        parts: ProjectParts,
+        paths: Vec<String>,
+        default_paths: &[&str],
+        uid: &str,
+    ) -> Result<domain::Project, Status> {
+        let (id, options) = update_options(parts, paths, default_paths)?;
+        self.project_app
+            .update(&id, uid, options)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Same as [`GrpcProjectServer::update_with_mask`] but for all the given updates at once.
+    async fn batch_update_with_mask(
+        &self,
+        updates: Vec<(ProjectParts, Vec<String>)>,
+        default_paths: &[&str],
+        uid: &str,
+    ) -> Result<Vec<(String, crate::result::Result<domain::Project>)>, Status> {
+        let updates = updates
+            .into_iter()
+            .map(|(parts, paths)| update_options(parts, paths, default_paths))
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        self.project_app
+            .batch_update(updates, uid)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Shares the given project with the given user under the given role, which must be specified.
+    async fn share_with_role(
+        &self,
+        id: &str,
+        user_id: &str,
+        role: Option<domain::Role>,
+        uid: &str,
+    ) -> Result<domain::Project, Status> {
+        let Some(role) = role else {
+            return Err(Error::InvalidFormat("role").into());
+        };
+
+        self.project_app
+            .share(id, user_id, role, uid)
+            .await
+            .map_err(Into::into)
+    }
 }
 
 #[tonic::async_trait]
-impl<P: 'static + ProjectRepository + Sync + Send, B: 'static + EventBus + Sync + Send>
-    ProjectService for GrpcProjectServer<P, B>
+impl<
+        P: 'static + ProjectRepository + Sync + Send,
+        B: 'static + EventBus + Sync + Send,
+        T: 'static + TokenService + Sync + Send,
+        R: 'static + TemplateRepository + Sync + Send,
+    > ProjectService for GrpcProjectServer<P, B, T, R>
 {
     async fn get(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
-        let uid = grpc::get_header(&request, self.uid_header)?;
+        let uid = grpc::get_uid(&request)?;
         let msg_ref = request.into_inner();
 
         self.project_app
@@ -42,45 +331,308 @@ impl<P: 'static + ProjectRepository + Sync + Send, B: 'static + EventBus + Sync
             .map_err(Into::into)
     }
 
-    async fn list(&self, request: Request<Empty>) -> Result<Response<ProjectList>, Status> {
-        let uid = grpc::get_header(&request, self.uid_header)?;
+    async fn list(
+        &self,
+        request: Request<ListProjectsRequest>,
+    ) -> Result<Response<ProjectList>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let Some(sort_by) = SortField::from_i32(msg_ref.sort_by) else {
+            return Err(Error::InvalidFormat("sort_by").into());
+        };
+
+        let options = ListOptions {
+            // clients predating pagination expect every project at once
+            page_size: (msg_ref.page_size != 0).then_some(msg_ref.page_size as usize),
+            page_token: (!msg_ref.page_token.is_empty()).then_some(msg_ref.page_token),
+            sort_by: sort_by.into(),
+            filter: msg_ref.filter.map(Into::into).unwrap_or_default(),
+        };
 
         self.project_app
-            .list(&uid)
+            .list(&uid, options)
             .await
             .map(|projects| Response::new(projects.into()))
             .map_err(Into::into)
     }
 
     async fn create(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
-        let uid = grpc::get_header(&request, self.uid_header)?;
+        let uid = grpc::get_uid(&request)?;
+        let idempotency_key = grpc::get_optional_header(&request, grpc::IDEMPOTENCY_KEY_HEADER)?;
+        let msg_ref = request.into_inner();
+
+        let options = CreateOptions {
+            description: msg_ref.description,
+            highlight: msg_ref.highlight,
+            idempotency_key,
+            ..Default::default()
+        };
+
+        self.create_from_template(&msg_ref.name, &msg_ref.template_id, &uid, options)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn duplicate(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
         let msg_ref = request.into_inner();
 
         self.project_app
-            .create(
+            .duplicate(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn update(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let parts = request.into_inner().into();
+
+        self.update_with_mask(parts, Vec::new(), &LEGACY_UPDATABLE_PATHS, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn update_masked(
+        &self,
+        request: Request<UpdateProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let (parts, paths) = masked_parts(request.into_inner())?;
+
+        self.update_with_mask(parts, paths, &LEGACY_UPDATABLE_PATHS, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn batch_get_projects(
+        &self,
+        request: Request<BatchGetProjectsRequest>,
+    ) -> Result<Response<BatchProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .batch_get(&msg_ref.ids, &uid)
+            .await
+            .map(|results| Response::new(results.into()))
+            .map_err(Into::into)
+    }
+
+    async fn batch_update_projects(
+        &self,
+        request: Request<BatchUpdateProjectsRequest>,
+    ) -> Result<Response<BatchProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let updates = request
+            .into_inner()
+            .requests
+            .into_iter()
+            .map(masked_parts)
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        self.batch_update_with_mask(updates, &LEGACY_UPDATABLE_PATHS, &uid)
+            .await
+            .map(|results| Response::new(results.into()))
+    }
+
+    async fn pin(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .pin(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn unpin(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .unpin(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn transfer_ownership(
+        &self,
+        request: Request<TransferOwnershipRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .transfer(&msg_ref.id, &msg_ref.user_id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn share(&self, request: Request<ShareRequest>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let role = Role::from_i32(msg_ref.role).and_then(Into::into);
+        self.share_with_role(&msg_ref.id, &msg_ref.user_id, role, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn unshare(&self, request: Request<ShareRequest>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .unshare(&msg_ref.id, &msg_ref.user_id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn create_share_token(
+        &self,
+        request: Request<CreateShareTokenRequest>,
+    ) -> Result<Response<ShareToken>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let ttl = (msg_ref.ttl > 0).then(|| Duration::from_secs(msg_ref.ttl));
+        self.share_app
+            .create(&msg_ref.id, ttl, &uid)
+            .await
+            .map(|token| Response::new(token.into()))
+            .map_err(Into::into)
+    }
+
+    async fn revoke_share_token(
+        &self,
+        request: Request<RevokeShareTokenRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .revoke(&msg_ref.id, &msg_ref.token_id, &uid)
+            .await
+            .map(|_| Response::new(Empty {}))
+            .map_err(Into::into)
+    }
+
+    async fn get_shared(
+        &self,
+        request: Request<SharedProjectRequest>,
+    ) -> Result<Response<ProjectWithCardinalities>, Status> {
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .get(&msg_ref.token)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn list_shared_entities(
+        &self,
+        request: Request<SharedProjectRequest>,
+    ) -> Result<Response<SharedEntityList>, Status> {
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .list_entities(&msg_ref.token)
+            .await
+            .map(|entities| Response::new(entities.into()))
+            .map_err(Into::into)
+    }
+
+    async fn save_template(
+        &self,
+        request: Request<SaveTemplateRequest>,
+    ) -> Result<Response<Template>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.template_app
+            .save(
+                &msg_ref.project_id,
                 &msg_ref.name,
+                &msg_ref.description,
                 &uid,
-                CreateOptions {
-                    description: msg_ref.description.to_string(),
-                    highlight: msg_ref.highlight,
-                    ..Default::default()
-                },
             )
             .await
-            .map(|project| Response::new(project.into()))
+            .map(|template| Response::new(template.into()))
             .map_err(Into::into)
     }
 
-    async fn update(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
-        let uid = grpc::get_header(&request, self.uid_header)?;
+    async fn list_templates(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<TemplateList>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        self.template_app
+            .list(&uid)
+            .await
+            .map(|templates| Response::new(templates.into()))
+            .map_err(Into::into)
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<ProjectChange, Status>> + Send>>;
+
+    async fn watch(&self, request: Request<Empty>) -> Result<Response<Self::WatchStream>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        let changes = self.project_app.watch(&uid).map(|change| Ok(change.into()));
+
+        Ok(Response::new(Box::pin(changes)))
+    }
+
+    async fn delete(&self, request: Request<Project>) -> Result<Response<Empty>, Status> {
+        let uid = grpc::get_uid(&request)?;
         let msg_ref = request.into_inner();
 
         self.project_app
-            .update(&msg_ref.id, &msg_ref.name, &msg_ref.description, &uid)
+            .delete(&msg_ref.id, &uid)
+            .await
+            .map(|_| Response::new(Empty {}))
+            .map_err(Into::into)
+    }
+
+    async fn list_deleted(&self, request: Request<Empty>) -> Result<Response<ProjectList>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        self.project_app
+            .list_deleted(&uid)
+            .await
+            .map(|projects| Response::new(projects.into()))
+            .map_err(Into::into)
+    }
+
+    async fn restore(&self, request: Request<Project>) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .restore(&msg_ref.id, &uid)
             .await
             .map(|project| Response::new(project.into()))
             .map_err(Into::into)
     }
+
+    async fn purge(&self, request: Request<Project>) -> Result<Response<Empty>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .purge(&msg_ref.id, &uid)
+            .await
+            .map(|_| Response::new(Empty {}))
+            .map_err(Into::into)
+    }
 }
 
 impl From<domain::Project> for Project {
@@ -90,6 +642,26 @@ impl From<domain::Project> for Project {
             name: value.name,
             description: value.description,
             highlight: value.highlight,
+            members: value.members.into_iter().map(Into::into).collect(),
+            fields: value.fields.into_iter().collect(),
+            template_id: "".to_string(),
+            version: value.version,
+        }
+    }
+}
+
+impl_common_conversions!(TemplateList, SharedEntityList);
+
+impl From<(domain::ShareToken, String)> for ShareToken {
+    fn from((share_token, token): (domain::ShareToken, String)) -> Self {
+        Self {
+            id: share_token.id,
+            token,
+            expires_at: share_token
+                .expires_at
+                .and_then(|expires_at| expires_at.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs() as i64)
+                .unwrap_or_default(),
         }
     }
 }
@@ -107,6 +679,32 @@ impl From<Vec<domain::ProjectWithCardinalities>> for ProjectList {
     fn from(value: Vec<domain::ProjectWithCardinalities>) -> Self {
         Self {
             projects: value.into_iter().map(Into::into).collect(),
+            next_page_token: "".to_string(),
+        }
+    }
+}
+
+impl From<domain::ProjectPage> for ProjectList {
+    fn from(value: domain::ProjectPage) -> Self {
+        Self {
+            projects: value.projects.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ProjectFilter> for Filter {
+    fn from(value: ProjectFilter) -> Self {
+        let timestamp = |secs: i64| UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64);
+
+        Self {
+            name: (!value.name.is_empty()).then_some(value.name),
+            highlight: value.highlight,
+            created_after: value.created_after.map(timestamp),
+            created_before: value.created_before.map(timestamp),
+            updated_after: value.updated_after.map(timestamp),
+            updated_before: value.updated_before.map(timestamp),
+            has_reference: value.has_reference,
         }
     }
 }
@@ -146,3 +744,38 @@ impl From<domain::Cardinalities> for Vec<Cardinality> {
         cardinalities
     }
 }
+
+/// Returns the id of the project to be updated and the changes to be applied on it as determined by the
+/// given update mask paths, or the default ones if empty, taking the new values from the given parts.
+fn update_options(
+    parts: ProjectParts,
+    mut paths: Vec<String>,
+    default_paths: &[&str],
+) -> Result<(String, UpdateOptions), Status> {
+    if paths.is_empty() {
+        paths = default_paths.iter().map(ToString::to_string).collect();
+    }
+
+    let mut options = UpdateOptions {
+        version: (parts.version > 0).then_some(parts.version),
+        ..Default::default()
+    };
+
+    for path in paths {
+        match path.as_str() {
+            "name" => options.name = Some(parts.name.clone()),
+            "description" => options.description = Some(parts.description.clone()),
+            "fields" => options.fields = Some(parts.fields.clone().into_iter().collect()),
+            _ => {
+                warn!(
+                    "{} unknown update mask path {}",
+                    Error::InvalidFormat("update_mask"),
+                    path
+                );
+                return Err(Error::InvalidFormat("update_mask").into());
+            }
+        }
+    }
+
+    Ok((parts.id, options))
+}
@@ -0,0 +1,502 @@
+//! Infrastructure layer for serving the project's aplication as the versioned `agora.project.v1` gRPC
+//! service.
+
+use super::{GrpcProjectServer, UPDATABLE_PATHS};
+use crate::grpc;
+use crate::project::{
+    application::{
+        CreateOptions, EventBus, Filter, ListOptions, ProjectRepository, SortBy, TokenService,
+    },
+    domain,
+};
+use crate::result::Error;
+use crate::template::{application::TemplateRepository, domain as template_domain};
+use futures_util::{Stream, StreamExt};
+use prost_types::Timestamp;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+use tonic::{Request, Response, Status};
+
+// Import the generated rust code into module
+mod proto {
+    tonic::include_proto!("agora.project.v1");
+}
+
+// Proto generated server traits
+use proto::project_service_server::ProjectService;
+pub use proto::project_service_server::ProjectServiceServer;
+
+// Proto message structs
+use proto::{
+    batch_project_result, BatchGetProjectsRequest, BatchProjectResult, BatchProjectsResponse,
+    BatchUpdateProjectsRequest, Cardinalities, ChangeKind, CreateProjectRequest,
+    CreateShareTokenRequest, DeleteProjectRequest, DuplicateProjectRequest, GetProjectRequest,
+    GetSharedProjectRequest, ListDeletedProjectsRequest, ListProjectsRequest, ListProjectsResponse,
+    ListSharedProjectEntitiesRequest, ListSharedProjectEntitiesResponse, ListTemplatesRequest,
+    ListTemplatesResponse, Member, PinProjectRequest, Project, ProjectChange, ProjectFilter,
+    ProjectSummary, PurgeProjectRequest, RestoreProjectRequest, RevokeShareTokenRequest, Role,
+    SaveTemplateRequest, ShareProjectRequest, ShareToken, SortField, Template, TemplateEntity,
+    TransferProjectOwnershipRequest, UnpinProjectRequest, UnshareProjectRequest,
+    UpdateProjectRequest, WatchProjectsRequest,
+};
+
+#[tonic::async_trait]
+impl<
+        P: 'static + ProjectRepository + Sync + Send,
+        B: 'static + EventBus + Sync + Send,
+        T: 'static + TokenService + Sync + Send,
+        R: 'static + TemplateRepository + Sync + Send,
+    > ProjectService for GrpcProjectServer<P, B, T, R>
+{
+    async fn get_project(
+        &self,
+        request: Request<GetProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .get(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn list_projects(
+        &self,
+        request: Request<ListProjectsRequest>,
+    ) -> Result<Response<ListProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let Some(sort_by) = SortField::from_i32(msg_ref.sort_by) else {
+            return Err(Error::InvalidFormat("sort_by").into());
+        };
+
+        let options = ListOptions {
+            page_size: Some(msg_ref.page_size as usize),
+            page_token: (!msg_ref.page_token.is_empty()).then_some(msg_ref.page_token),
+            sort_by: sort_by.into(),
+            filter: msg_ref
+                .filter
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+        };
+
+        self.project_app
+            .list(&uid, options)
+            .await
+            .map(|page| Response::new(page.into()))
+            .map_err(Into::into)
+    }
+
+    async fn create_project(
+        &self,
+        request: Request<CreateProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let idempotency_key = grpc::get_optional_header(&request, grpc::IDEMPOTENCY_KEY_HEADER)?;
+        let msg_ref = request.into_inner();
+
+        let options = CreateOptions {
+            description: msg_ref.description,
+            highlight: msg_ref.highlight,
+            idempotency_key,
+            ..Default::default()
+        };
+
+        self.create_from_template(&msg_ref.name, &msg_ref.template_id, &uid, options)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn update_project(
+        &self,
+        request: Request<UpdateProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let (parts, paths) = masked_parts(request.into_inner())?;
+
+        self.update_with_mask(parts, paths, &UPDATABLE_PATHS, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn delete_project(
+        &self,
+        request: Request<DeleteProjectRequest>,
+    ) -> Result<Response<()>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .delete(&msg_ref.id, &uid)
+            .await
+            .map(Response::new)
+            .map_err(Into::into)
+    }
+
+    async fn list_deleted_projects(
+        &self,
+        request: Request<ListDeletedProjectsRequest>,
+    ) -> Result<Response<ListProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        self.project_app
+            .list_deleted(&uid)
+            .await
+            .map(|projects| Response::new(projects.into()))
+            .map_err(Into::into)
+    }
+
+    async fn restore_project(
+        &self,
+        request: Request<RestoreProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .restore(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn purge_project(
+        &self,
+        request: Request<PurgeProjectRequest>,
+    ) -> Result<Response<()>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .purge(&msg_ref.id, &uid)
+            .await
+            .map(Response::new)
+            .map_err(Into::into)
+    }
+
+    async fn duplicate_project(
+        &self,
+        request: Request<DuplicateProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .duplicate(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn pin_project(
+        &self,
+        request: Request<PinProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .pin(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn unpin_project(
+        &self,
+        request: Request<UnpinProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .unpin(&msg_ref.id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn batch_get_projects(
+        &self,
+        request: Request<BatchGetProjectsRequest>,
+    ) -> Result<Response<BatchProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .batch_get(&msg_ref.ids, &uid)
+            .await
+            .map(|results| Response::new(results.into()))
+            .map_err(Into::into)
+    }
+
+    async fn batch_update_projects(
+        &self,
+        request: Request<BatchUpdateProjectsRequest>,
+    ) -> Result<Response<BatchProjectsResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let updates = request
+            .into_inner()
+            .requests
+            .into_iter()
+            .map(masked_parts)
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        self.batch_update_with_mask(updates, &UPDATABLE_PATHS, &uid)
+            .await
+            .map(|results| Response::new(results.into()))
+    }
+
+    async fn transfer_project_ownership(
+        &self,
+        request: Request<TransferProjectOwnershipRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .transfer(&msg_ref.id, &msg_ref.user_id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn share_project(
+        &self,
+        request: Request<ShareProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let role = Role::from_i32(msg_ref.role).and_then(Into::into);
+        self.share_with_role(&msg_ref.id, &msg_ref.user_id, role, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+    }
+
+    async fn unshare_project(
+        &self,
+        request: Request<UnshareProjectRequest>,
+    ) -> Result<Response<Project>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.project_app
+            .unshare(&msg_ref.id, &msg_ref.user_id, &uid)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn create_share_token(
+        &self,
+        request: Request<CreateShareTokenRequest>,
+    ) -> Result<Response<ShareToken>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        let ttl = msg_ref
+            .ttl
+            .map(Duration::try_from)
+            .transpose()
+            .map_err(|err| {
+                warn!("{} parsing ttl: {}", Error::InvalidFormat("ttl"), err);
+                Into::<Status>::into(Error::InvalidFormat("ttl"))
+            })?
+            .filter(|ttl| !ttl.is_zero());
+
+        self.share_app
+            .create(&msg_ref.id, ttl, &uid)
+            .await
+            .map(|token| Response::new(token.into()))
+            .map_err(Into::into)
+    }
+
+    async fn revoke_share_token(
+        &self,
+        request: Request<RevokeShareTokenRequest>,
+    ) -> Result<Response<()>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .revoke(&msg_ref.id, &msg_ref.token_id, &uid)
+            .await
+            .map(Response::new)
+            .map_err(Into::into)
+    }
+
+    async fn get_shared_project(
+        &self,
+        request: Request<GetSharedProjectRequest>,
+    ) -> Result<Response<ProjectSummary>, Status> {
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .get(&msg_ref.token)
+            .await
+            .map(|project| Response::new(project.into()))
+            .map_err(Into::into)
+    }
+
+    async fn list_shared_project_entities(
+        &self,
+        request: Request<ListSharedProjectEntitiesRequest>,
+    ) -> Result<Response<ListSharedProjectEntitiesResponse>, Status> {
+        let msg_ref = request.into_inner();
+
+        self.share_app
+            .list_entities(&msg_ref.token)
+            .await
+            .map(|entities| Response::new(entities.into()))
+            .map_err(Into::into)
+    }
+
+    async fn save_template(
+        &self,
+        request: Request<SaveTemplateRequest>,
+    ) -> Result<Response<Template>, Status> {
+        let uid = grpc::get_uid(&request)?;
+        let msg_ref = request.into_inner();
+
+        self.template_app
+            .save(
+                &msg_ref.project_id,
+                &msg_ref.name,
+                &msg_ref.description,
+                &uid,
+            )
+            .await
+            .map(|template| Response::new(template.into()))
+            .map_err(Into::into)
+    }
+
+    async fn list_templates(
+        &self,
+        request: Request<ListTemplatesRequest>,
+    ) -> Result<Response<ListTemplatesResponse>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        self.template_app
+            .list(&uid)
+            .await
+            .map(|templates| Response::new(templates.into()))
+            .map_err(Into::into)
+    }
+
+    type WatchProjectsStream = Pin<Box<dyn Stream<Item = Result<ProjectChange, Status>> + Send>>;
+
+    async fn watch_projects(
+        &self,
+        request: Request<WatchProjectsRequest>,
+    ) -> Result<Response<Self::WatchProjectsStream>, Status> {
+        let uid = grpc::get_uid(&request)?;
+
+        let changes = self.project_app.watch(&uid).map(|change| Ok(change.into()));
+
+        Ok(Response::new(Box::pin(changes)))
+    }
+}
+
+impl From<domain::Project> for Project {
+    fn from(value: domain::Project) -> Self {
+        Self {
+            owner: value.meta.created_by().unwrap_or_default().to_string(),
+            created_at: value.meta.created_at().map(Into::into),
+            updated_at: value.meta.updated_at().map(Into::into),
+            deleted_at: value.meta.deleted_at().map(Into::into),
+            id: value.id,
+            name: value.name,
+            description: value.description,
+            highlight: value.highlight,
+            members: value.members.into_iter().map(Into::into).collect(),
+            fields: value.fields.into_iter().collect(),
+            reference: value.reference,
+            version: value.version,
+        }
+    }
+}
+
+impl_common_conversions!(ListTemplatesResponse, ListSharedProjectEntitiesResponse);
+
+impl From<domain::Cardinalities> for Cardinalities {
+    fn from(value: domain::Cardinalities) -> Self {
+        Self {
+            characters: value.total_characters,
+            objects: value.total_objects,
+            locations: value.total_locations,
+            events: value.total_events,
+        }
+    }
+}
+
+impl From<domain::ProjectWithCardinalities> for ProjectSummary {
+    fn from(value: domain::ProjectWithCardinalities) -> Self {
+        Self {
+            project: Some(value.project.into()),
+            cardinalities: Some(value.cardinalities.into()),
+        }
+    }
+}
+
+impl From<domain::ProjectPage> for ListProjectsResponse {
+    fn from(value: domain::ProjectPage) -> Self {
+        Self {
+            projects: value.projects.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<Vec<domain::ProjectWithCardinalities>> for ListProjectsResponse {
+    fn from(value: Vec<domain::ProjectWithCardinalities>) -> Self {
+        Self {
+            projects: value.into_iter().map(Into::into).collect(),
+            next_page_token: "".to_string(),
+        }
+    }
+}
+
+impl From<(domain::ShareToken, String)> for ShareToken {
+    fn from((share_token, token): (domain::ShareToken, String)) -> Self {
+        Self {
+            id: share_token.id,
+            token,
+            expires_at: share_token.expires_at.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<ProjectFilter> for Filter {
+    type Error = Status;
+
+    fn try_from(value: ProjectFilter) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: (!value.name.is_empty()).then_some(value.name),
+            highlight: value.highlight,
+            created_after: system_time(value.created_after, "filter.created_after")?,
+            created_before: system_time(value.created_before, "filter.created_before")?,
+            updated_after: system_time(value.updated_after, "filter.updated_after")?,
+            updated_before: system_time(value.updated_before, "filter.updated_before")?,
+            has_reference: value.has_reference,
+        })
+    }
+}
+
+/// Returns the system time corresponding to the given timestamp, if any, or an error if it is out of range.
+fn system_time(
+    timestamp: Option<Timestamp>,
+    field: &'static str,
+) -> Result<Option<SystemTime>, Status> {
+    timestamp
+        .map(SystemTime::try_from)
+        .transpose()
+        .map_err(|err| {
+            warn!("{} parsing timestamp: {}", Error::InvalidFormat(field), err);
+            Error::InvalidFormat(field).into()
+        })
+}
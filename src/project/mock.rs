@@ -0,0 +1,427 @@
+//! In-memory implementations of the project's ports, for testing those applications depending on them.
+
+use super::application::{
+    Cursor, EventBus, Filter, Page, ProjectApplication, ProjectRepository, SortBy, SortKey,
+};
+use super::domain::{Cardinalities, Project, ProjectWithCardinalities, Role};
+use crate::result::{Error, Result};
+use crate::template::domain::{Entity, EntityKind};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Repository keeping projects, along with their child entities and idempotency keys, in memory.
+#[derive(Default)]
+pub struct InMemoryProjectRepository {
+    projects: Mutex<HashMap<String, Project>>,
+    entities: Mutex<HashMap<String, Vec<Entity>>>,
+    /// Expiration time and bound project, if any, by user and key.
+    idempotency_keys: Mutex<HashMap<(String, String), (SystemTime, Option<String>)>>,
+    created: AtomicUsize,
+}
+
+impl InMemoryProjectRepository {
+    /// Returns the project with the given id as currently persisted, regardless of its members.
+    pub fn get(&self, id: &str) -> Option<Project> {
+        self.projects.lock().unwrap().get(id).cloned()
+    }
+
+    /// Returns the child entities of the project with the given id as currently persisted.
+    pub fn entities(&self, id: &str) -> Vec<Entity> {
+        self.entities
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns all the persisted projects satisfying the given predicate, along with their cardinalities.
+    fn with_cardinalities(
+        &self,
+        predicate: impl Fn(&Project) -> bool,
+    ) -> Vec<ProjectWithCardinalities> {
+        self.projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|project| predicate(project))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|project| ProjectWithCardinalities {
+                cardinalities: self.cardinalities(&project.id),
+                project,
+            })
+            .collect()
+    }
+
+    fn cardinalities(&self, id: &str) -> Cardinalities {
+        let entities = self.entities(id);
+        let total = |kind: EntityKind| {
+            entities
+                .iter()
+                .filter(|entity| entity.kind() == kind)
+                .count() as i32
+        };
+
+        Cardinalities {
+            total_characters: total(EntityKind::Character),
+            total_objects: total(EntityKind::Object),
+            total_locations: total(EntityKind::Location),
+            total_events: total(EntityKind::Event),
+        }
+    }
+
+    /// Inserts the given project under a new id, along with the given entities as its children.
+    fn insert(&self, project: &mut Project, entities: Vec<Entity>) {
+        let created = self.created.fetch_add(1, Ordering::Relaxed);
+        project.id = format!("project:{}", created + 1);
+
+        self.entities
+            .lock()
+            .unwrap()
+            .insert(project.id.clone(), entities);
+        self.projects
+            .lock()
+            .unwrap()
+            .insert(project.id.clone(), project.clone());
+    }
+
+    /// Overwrites the persisted project with the given one as long as both are at the same version, increasing
+    /// it. Otherwise a conflict is returned.
+    fn persist(projects: &mut HashMap<String, Project>, project: &mut Project) -> Result<()> {
+        let Some(persisted) = projects.get_mut(&project.id) else {
+            return Err(Error::NotFound);
+        };
+
+        if persisted.version != project.version {
+            return Err(Error::Conflict);
+        }
+
+        project.version += 1;
+        *persisted = project.clone();
+        Ok(())
+    }
+
+    /// Removes the projects with any of the given ids along with all their child entities.
+    fn remove(&self, ids: &[String]) {
+        let mut projects = self.projects.lock().unwrap();
+        let mut entities = self.entities.lock().unwrap();
+        for id in ids {
+            projects.remove(id);
+            entities.remove(id);
+        }
+    }
+}
+
+/// Returns the position of the given project in the given order, as told by the cursor pointing right after it.
+fn position(project: &Project, sort_by: SortBy) -> Cursor {
+    let secs = |time: Option<SystemTime>| time.map(secs_since_epoch).unwrap_or_default();
+    let key = match sort_by {
+        SortBy::UpdatedAt => SortKey::Secs(secs(
+            project.meta.updated_at().or(project.meta.created_at()),
+        )),
+        SortBy::CreatedAt => SortKey::Secs(secs(project.meta.created_at())),
+        SortBy::Name => SortKey::Name(project.name.clone()),
+    };
+
+    Cursor {
+        highlight: project.highlight,
+        key,
+        id: project.id.clone(),
+    }
+}
+
+/// Compares two positions as the repository sorts them: highlighted first, then by the newest dates or the
+/// lowest names, and then by the lowest ids.
+fn compare(a: &Cursor, b: &Cursor) -> CmpOrdering {
+    let by_key = match (&a.key, &b.key) {
+        (SortKey::Secs(a), SortKey::Secs(b)) => b.cmp(a),
+        (SortKey::Name(a), SortKey::Name(b)) => a.cmp(b),
+        _ => CmpOrdering::Equal,
+    };
+
+    b.highlight
+        .cmp(&a.highlight)
+        .then(by_key)
+        .then_with(|| a.id.cmp(&b.id))
+}
+
+fn secs_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns true if, and only if, the given project satisfies all the conditions of the given filter.
+fn matches(project: &Project, filter: &Filter) -> bool {
+    let created_at = project.meta.created_at().map(secs_since_epoch);
+    let updated_at = project
+        .meta
+        .updated_at()
+        .or(project.meta.created_at())
+        .map(secs_since_epoch);
+    let after = |time: Option<u64>, bound: Option<SystemTime>| {
+        bound.map_or(true, |bound| time >= Some(secs_since_epoch(bound)))
+    };
+    let before = |time: Option<u64>, bound: Option<SystemTime>| {
+        bound.map_or(true, |bound| {
+            time.is_some_and(|time| time < secs_since_epoch(bound))
+        })
+    };
+
+    filter.name.as_ref().map_or(true, |name| {
+        project.name.to_lowercase().contains(&name.to_lowercase())
+    }) && filter
+        .highlight
+        .map_or(true, |highlight| project.highlight == highlight)
+        && after(created_at, filter.created_after)
+        && before(created_at, filter.created_before)
+        && after(updated_at, filter.updated_after)
+        && before(updated_at, filter.updated_before)
+        && filter.has_reference.map_or(true, |has_reference| {
+            project.reference.is_some() == has_reference
+        })
+}
+
+#[async_trait::async_trait]
+impl ProjectRepository for InMemoryProjectRepository {
+    async fn find(&self, id: &str, uid: &str) -> Result<Project> {
+        self.get(id)
+            .filter(|project| project.meta.deleted_at().is_none())
+            .filter(|project| project.role_of(uid).is_some())
+            .ok_or(Error::NotFound)
+    }
+
+    async fn find_all(
+        &self,
+        uid: &str,
+        filter: &Filter,
+        page: &Page,
+    ) -> Result<Vec<ProjectWithCardinalities>> {
+        let mut projects = self.with_cardinalities(|project| {
+            project.meta.deleted_at().is_none()
+                && project.role_of(uid).is_some()
+                && matches(project, filter)
+                && page.cursor.as_ref().map_or(true, |cursor| {
+                    compare(&position(project, page.sort_by), cursor) == CmpOrdering::Greater
+                })
+        });
+
+        projects.sort_by(|a, b| {
+            compare(
+                &position(&a.project, page.sort_by),
+                &position(&b.project, page.sort_by),
+            )
+        });
+
+        if let Some(limit) = page.limit {
+            projects.truncate(limit);
+        }
+
+        Ok(projects)
+    }
+
+    async fn find_many(&self, ids: &[String]) -> Result<Vec<Project>> {
+        Ok(self
+            .projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|project| project.meta.deleted_at().is_none())
+            .filter(|project| ids.contains(&project.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn find_by_reference(&self, reference: &str) -> Result<Project> {
+        self.projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|project| project.meta.deleted_at().is_none())
+            .find(|project| project.reference.as_deref() == Some(reference))
+            .cloned()
+            .ok_or(Error::NotFound)
+    }
+
+    async fn find_with_cardinalities(&self, id: &str) -> Result<ProjectWithCardinalities> {
+        self.with_cardinalities(|project| project.id == id && project.meta.deleted_at().is_none())
+            .pop()
+            .ok_or(Error::NotFound)
+    }
+
+    async fn find_by_idempotency_key(&self, key: &str, uid: &str) -> Result<Project> {
+        let project_id = self
+            .idempotency_keys
+            .lock()
+            .unwrap()
+            .get(&(uid.to_string(), key.to_string()))
+            .filter(|(expires_at, _)| *expires_at > SystemTime::now())
+            .and_then(|(_, project_id)| project_id.clone())
+            .ok_or(Error::NotFound)?;
+
+        self.get(&project_id).ok_or(Error::NotFound)
+    }
+
+    async fn find_deleted(&self, id: &str, uid: &str) -> Result<Project> {
+        self.get(id)
+            .filter(|project| project.meta.deleted_at().is_some())
+            .filter(|project| project.role_of(uid).is_some())
+            .ok_or(Error::NotFound)
+    }
+
+    async fn find_all_deleted(&self, uid: &str) -> Result<Vec<ProjectWithCardinalities>> {
+        Ok(self.with_cardinalities(|project| {
+            project.meta.deleted_at().is_some() && project.role_of(uid) == Some(Role::Owner)
+        }))
+    }
+
+    async fn create(&self, project: &mut Project, entities: &[Entity]) -> Result<()> {
+        self.insert(project, entities.to_vec());
+        Ok(())
+    }
+
+    async fn reserve_idempotency_key(
+        &self,
+        key: &str,
+        uid: &str,
+        expires_at: SystemTime,
+    ) -> Result<()> {
+        let now = SystemTime::now();
+        let mut keys = self.idempotency_keys.lock().unwrap();
+        keys.retain(|_, (expires_at, _)| *expires_at > now);
+
+        let key = (uid.to_string(), key.to_string());
+        if keys.contains_key(&key) {
+            return Err(Error::AlreadyExists);
+        }
+
+        keys.insert(key, (expires_at, None));
+        Ok(())
+    }
+
+    async fn bind_idempotency_key(&self, key: &str, uid: &str, project: &Project) -> Result<()> {
+        if let Some((_, project_id)) = self
+            .idempotency_keys
+            .lock()
+            .unwrap()
+            .get_mut(&(uid.to_string(), key.to_string()))
+        {
+            *project_id = Some(project.id.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str, uid: &str) -> Result<()> {
+        self.idempotency_keys
+            .lock()
+            .unwrap()
+            .remove(&(uid.to_string(), key.to_string()));
+        Ok(())
+    }
+
+    async fn duplicate(&self, source: &Project, project: &mut Project) -> Result<()> {
+        let entities = self.entities(&source.id);
+        self.insert(project, entities);
+        Ok(())
+    }
+
+    async fn update(&self, project: &mut Project) -> Result<()> {
+        Self::persist(&mut self.projects.lock().unwrap(), project)
+    }
+
+    async fn update_many(&self, projects: &mut [Project]) -> Result<Vec<Result<()>>> {
+        let mut persisted = self.projects.lock().unwrap();
+        Ok(projects
+            .iter_mut()
+            .map(|project| Self::persist(&mut persisted, project))
+            .collect())
+    }
+
+    async fn find_entities(&self, project: &Project) -> Result<Vec<Entity>> {
+        Ok(self.entities(&project.id))
+    }
+
+    async fn transfer(&self, project: &mut Project) -> Result<()> {
+        Self::persist(&mut self.projects.lock().unwrap(), project)
+    }
+
+    async fn delete(&self, project: &mut Project) -> Result<()> {
+        Self::persist(&mut self.projects.lock().unwrap(), project)
+    }
+
+    async fn purge(&self, project: &Project) -> Result<()> {
+        self.remove(&[project.id.clone()]);
+        Ok(())
+    }
+
+    async fn purge_deleted_before(&self, deleted_before: SystemTime) -> Result<()> {
+        let purged: Vec<String> = self
+            .projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|project| {
+                project
+                    .meta
+                    .deleted_at()
+                    .is_some_and(|deleted_at| deleted_at < deleted_before)
+            })
+            .map(|project| project.id.clone())
+            .collect();
+
+        self.remove(&purged);
+        Ok(())
+    }
+}
+
+/// Event bus recording the id of every project a file event has been emitted for, by event.
+#[derive(Default)]
+pub struct RecordingEventBus {
+    pub created: Mutex<Vec<String>>,
+    pub updated: Mutex<Vec<String>>,
+    pub deleted: Mutex<Vec<String>>,
+    pub transferred: Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl EventBus for RecordingEventBus {
+    async fn emit_file_created(&self, project: &Project) -> Result<()> {
+        self.created.lock().unwrap().push(project.id.clone());
+        Ok(())
+    }
+
+    async fn emit_file_updated(&self, project: &Project) -> Result<()> {
+        self.updated.lock().unwrap().push(project.id.clone());
+        Ok(())
+    }
+
+    async fn emit_file_deleted(&self, project: &Project) -> Result<()> {
+        self.deleted.lock().unwrap().push(project.id.clone());
+        Ok(())
+    }
+
+    async fn emit_file_transferred(&self, project: &Project) -> Result<()> {
+        self.transferred.lock().unwrap().push(project.id.clone());
+        Ok(())
+    }
+}
+
+/// Returns a project application on top of the given in-memory ports.
+pub fn project_application(
+    project_repo: Arc<InMemoryProjectRepository>,
+    event_bus: Arc<RecordingEventBus>,
+) -> ProjectApplication<InMemoryProjectRepository, RecordingEventBus> {
+    ProjectApplication {
+        project_repo,
+        event_bus,
+        changes: broadcast::channel(16).0,
+        idempotency_ttl: Duration::from_secs(60),
+    }
+}
@@ -1,4 +1,7 @@
 pub mod application;
 pub mod domain;
 pub mod grpc;
+#[cfg(test)]
+pub(crate) mod mock;
 pub mod repository;
+pub mod token;
@@ -1,28 +1,228 @@
 //! Infrastructure layer for managing projects persistency on SurrealDB.
 
 use super::{
-    application::ProjectRepository,
-    domain::{Cardinalities, Project, ProjectWithCardinalities},
+    application::{Filter, Page, ProjectRepository, SortBy, SortKey},
+    domain::{Cardinalities, Member, Project, ProjectWithCardinalities, Role, ShareToken},
 };
-use crate::metadata::repository::SurrealMetadata;
+use crate::metadata::{domain::Metadata, repository::SurrealMetadata};
 use crate::result::{Error, Result};
-use crate::surreal;
+use crate::surreal::{self, record_key};
+use crate::template::domain::{Entity, EntityKind};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
 use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use uuid::Uuid;
 
 const TABLENAME: &str = "project";
 
-const QUERY_FIND_PROJECT: &str =
-    "SELECT * FROM project WHERE id = $id AND meta.created_by = $created_by;";
+/// Tables holding the entities that belong to a project, linked as a record through their `project` field.
+/// Each table is named after the [`EntityKind`] it holds.
+const CHILD_TABLENAMES: [&str; 4] = ["character", "object", "location", "event"];
 
-const QUERY_FIND_ALL_PROJECTS_WITH_CARDINALITIES: &str = "SELECT *,
-count(project.characters) AS total_characters,
-count(project.objects) AS total_objects,
-count(project.locations) AS total_locations,
-count(project.events) AS total_events
+const QUERY_FIND_PROJECT: &str = "SELECT * FROM project
+WHERE id = $id
+AND (meta.created_by = $uid OR $uid INSIDE members.user_id)
+AND meta.deleted_at IS NONE;";
+
+/// Expands into the fields counting the child entities of each selected project, which link to it as a record.
+macro_rules! cardinality_fields {
+    () => {
+        "count((SELECT id FROM character WHERE project = $parent.id)) AS total_characters,
+count((SELECT id FROM object WHERE project = $parent.id)) AS total_objects,
+count((SELECT id FROM location WHERE project = $parent.id)) AS total_locations,
+count((SELECT id FROM event WHERE project = $parent.id)) AS total_events"
+    };
+}
+
+const QUERY_FIND_ALL_PROJECTS_WITH_CARDINALITIES: &str = concat!(
+    "SELECT *,\n",
+    cardinality_fields!(),
+    ",
+meta.created_at.secs_since_epoch AS created_at,
+meta.updated_at.secs_since_epoch OR meta.created_at.secs_since_epoch AS last_update
 FROM project
-WHERE meta.created_by = $created_by;";
+WHERE (meta.created_by = $uid OR $uid INSIDE members.user_id)
+AND meta.deleted_at IS NONE"
+);
+
+const QUERY_FIND_PROJECT_WITH_CARDINALITIES: &str = concat!(
+    "SELECT *,\n",
+    cardinality_fields!(),
+    "
+FROM project
+WHERE id = $id
+AND meta.deleted_at IS NONE;"
+);
+
+const QUERY_FIND_PROJECTS: &str = "SELECT * FROM project
+WHERE id INSIDE $ids
+AND meta.deleted_at IS NONE;";
+
+const QUERY_FIND_PROJECT_BY_REFERENCE: &str = "SELECT * FROM project
+WHERE reference = $reference
+AND meta.deleted_at IS NONE;";
+
+const QUERY_FIND_PROJECT_BY_IDEMPOTENCY_KEY: &str = "SELECT * FROM project
+WHERE id INSIDE (
+    SELECT VALUE project FROM idempotency_key
+    WHERE uid = $uid
+    AND key = $key
+    AND expires_at > $now
+);";
+
+const QUERY_DEFINE_IDEMPOTENCY_KEY_INDEX: &str =
+    "DEFINE INDEX idempotency_key_uid_key ON TABLE idempotency_key COLUMNS uid, key UNIQUE;";
+
+const QUERY_RESERVE_IDEMPOTENCY_KEY: &str = "DELETE idempotency_key WHERE expires_at <= $now;
+CREATE idempotency_key SET uid = $uid, key = $key, expires_at = $expires_at;";
+
+const QUERY_BIND_IDEMPOTENCY_KEY: &str = "UPDATE idempotency_key
+SET project = type::thing($tb, $project)
+WHERE uid = $uid
+AND key = $key;";
+
+const QUERY_RELEASE_IDEMPOTENCY_KEY: &str = "DELETE idempotency_key
+WHERE uid = $uid
+AND key = $key;";
+
+const QUERY_FIND_DELETED_PROJECT: &str = "SELECT * FROM project
+WHERE id = $id
+AND (meta.created_by = $uid OR $uid INSIDE members.user_id)
+AND meta.deleted_at IS NOT NONE;";
+
+const QUERY_FIND_ALL_DELETED_PROJECTS_WITH_CARDINALITIES: &str = concat!(
+    "SELECT *,\n",
+    cardinality_fields!(),
+    "
+FROM project
+WHERE (meta.created_by = $uid OR $uid INSIDE members[WHERE role = 'owner'].user_id)
+AND meta.deleted_at IS NOT NONE;"
+);
+
+const QUERY_FIND_PROJECT_CHILDREN: &str =
+    "SELECT *, type::string(id) AS id FROM character WHERE project = $project;
+SELECT *, type::string(id) AS id FROM object WHERE project = $project;
+SELECT *, type::string(id) AS id FROM location WHERE project = $project;
+SELECT *, type::string(id) AS id FROM event WHERE project = $project;";
+
+const QUERY_FIND_PROJECT_ENTITIES: &str =
+    "SELECT name, description FROM character WHERE project = $project;
+SELECT name, description FROM object WHERE project = $project;
+SELECT name, description FROM location WHERE project = $project;
+SELECT name, description FROM event WHERE project = $project;";
+
+const QUERY_UPDATE_PROJECT: &str = "UPDATE type::thing($tb, $key)
+CONTENT $content
+WHERE (version OR 0) = $version;";
+
+/// Children are only moved along if the project itself got transferred, that is, it is now at the new version.
+const QUERY_TRANSFER_PROJECT: &str = "BEGIN TRANSACTION;
+UPDATE project SET meta = $meta, members = $members, version = $version
+WHERE id = $id AND (version OR 0) = $expected;
+UPDATE character SET meta.created_by = $created_by
+WHERE project = $id AND (SELECT VALUE version FROM project WHERE id = $id)[0] = $version;
+UPDATE object SET meta.created_by = $created_by
+WHERE project = $id AND (SELECT VALUE version FROM project WHERE id = $id)[0] = $version;
+UPDATE location SET meta.created_by = $created_by
+WHERE project = $id AND (SELECT VALUE version FROM project WHERE id = $id)[0] = $version;
+UPDATE event SET meta.created_by = $created_by
+WHERE project = $id AND (SELECT VALUE version FROM project WHERE id = $id)[0] = $version;
+COMMIT TRANSACTION;";
+
+const QUERY_DELETE_PROJECT: &str = "UPDATE type::thing($tb, $key)
+MERGE $content
+WHERE (version OR 0) = $version;";
+
+/// Children are purged along with the project, so none of them is left pointing to a missing record.
+const QUERY_PURGE_PROJECT: &str = "BEGIN TRANSACTION;
+DELETE character WHERE project = type::thing($tb, $key);
+DELETE object WHERE project = type::thing($tb, $key);
+DELETE location WHERE project = type::thing($tb, $key);
+DELETE event WHERE project = type::thing($tb, $key);
+DELETE type::thing($tb, $key);
+COMMIT TRANSACTION;";
+
+const QUERY_PURGE_DELETED_PROJECTS: &str = "BEGIN TRANSACTION;
+LET $purged = (SELECT VALUE id FROM project
+    WHERE meta.deleted_at IS NOT NONE
+    AND meta.deleted_at.secs_since_epoch < $deleted_before);
+DELETE character WHERE project INSIDE $purged;
+DELETE object WHERE project INSIDE $purged;
+DELETE location WHERE project INSIDE $purged;
+DELETE event WHERE project INSIDE $purged;
+DELETE project WHERE id INSIDE $purged;
+COMMIT TRANSACTION;";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SurrealMember<'a> {
+    user_id: Cow<'a, str>,
+    role: Cow<'a, str>,
+}
+
+impl<'a> From<SurrealMember<'a>> for Member {
+    fn from(value: SurrealMember<'a>) -> Self {
+        Member {
+            user_id: value.user_id.into(),
+            role: value.role.parse().unwrap_or_else(|_| {
+                warn!(
+                    "{} parsing role {} of member {}",
+                    Error::InvalidFormat("role"),
+                    value.role,
+                    value.user_id
+                );
+                Role::Viewer
+            }),
+        }
+    }
+}
+
+impl<'a> From<&Member> for SurrealMember<'a> {
+    fn from(value: &Member) -> Self {
+        SurrealMember {
+            user_id: value.user_id.clone().into(),
+            role: value.role.to_string().into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SurrealShareToken<'a> {
+    id: Cow<'a, str>,
+    expires_at: Option<Cow<'a, SystemTime>>,
+}
+
+impl<'a> SurrealShareToken<'a> {
+    fn into_share_token(self, project_id: &str) -> ShareToken {
+        ShareToken {
+            id: self.id.into(),
+            project_id: project_id.to_string(),
+            expires_at: self.expires_at.map(|item| item.into_owned()),
+        }
+    }
+}
+
+impl<'a> From<&ShareToken> for SurrealShareToken<'a> {
+    fn from(value: &ShareToken) -> Self {
+        SurrealShareToken {
+            id: value.id.clone().into(),
+            expires_at: value.expires_at.map(Cow::Owned),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SurrealEntity<'a> {
+    name: Cow<'a, str>,
+    #[serde(default)]
+    description: Cow<'a, str>,
+    #[serde(default)]
+    project: Option<Thing>,
+    #[serde(default)]
+    meta: SurrealMetadata<'a>,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 struct SurrealProject<'a> {
@@ -32,17 +232,36 @@ struct SurrealProject<'a> {
     reference: Option<Cow<'a, str>>,
     meta: Cow<'a, SurrealMetadata<'a>>,
     highlight: bool,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    members: Vec<SurrealMember<'a>>,
+    #[serde(default)]
+    share_tokens: Vec<SurrealShareToken<'a>>,
+    #[serde(default)]
+    version: u64,
 }
 
 impl<'a> From<SurrealProject<'a>> for Project {
     fn from(value: SurrealProject<'a>) -> Self {
+        let id = value.id.to_string();
+        let share_tokens = value
+            .share_tokens
+            .into_iter()
+            .map(|token| token.into_share_token(&id))
+            .collect();
+
         Project {
-            id: value.id.to_string(),
+            id,
             name: value.name.into(),
             description: value.description.into(),
             reference: value.reference.map(Into::into),
             meta: value.meta.into_owned().into(),
             highlight: value.highlight,
+            fields: value.fields,
+            members: value.members.into_iter().map(Into::into).collect(),
+            share_tokens,
+            version: value.version,
         }
     }
 }
@@ -58,6 +277,10 @@ impl<'a> From<&Project> for SurrealProject<'a> {
             reference: value.reference.clone().map(Into::into),
             meta: Cow::Owned(metadata),
             highlight: value.highlight,
+            fields: value.fields.clone(),
+            members: value.members.iter().map(Into::into).collect(),
+            share_tokens: value.share_tokens.iter().map(Into::into).collect(),
+            version: value.version,
         }
     }
 }
@@ -70,6 +293,14 @@ struct SurrealProjectWithCardinalities<'a> {
     reference: Option<Cow<'a, str>>,
     meta: SurrealMetadata<'a>,
     highlight: bool,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    members: Vec<SurrealMember<'a>>,
+    #[serde(default)]
+    share_tokens: Vec<SurrealShareToken<'a>>,
+    #[serde(default)]
+    version: u64,
 
     #[serde(skip_serializing)]
     total_characters: i32,
@@ -83,13 +314,24 @@ struct SurrealProjectWithCardinalities<'a> {
 
 impl<'a> From<SurrealProjectWithCardinalities<'a>> for ProjectWithCardinalities {
     fn from(value: SurrealProjectWithCardinalities<'a>) -> Self {
+        let id = value.id.to_string();
+        let share_tokens = value
+            .share_tokens
+            .into_iter()
+            .map(|token| token.into_share_token(&id))
+            .collect();
+
         Self {
             project: Project {
-                id: value.id.to_string(),
+                id,
                 name: value.name.into(),
                 description: value.description.into(),
                 reference: value.reference.map(Into::into),
                 highlight: value.highlight,
+                fields: value.fields,
+                members: value.members.into_iter().map(Into::into).collect(),
+                share_tokens,
+                version: value.version,
                 meta: value.meta.into(),
             },
 
@@ -110,6 +352,10 @@ struct SurrealAnonymousProject<'a> {
     reference: Option<Cow<'a, str>>,
     meta: SurrealMetadata<'a>,
     highlight: bool,
+    fields: BTreeMap<String, String>,
+    members: Vec<SurrealMember<'a>>,
+    share_tokens: Vec<SurrealShareToken<'a>>,
+    version: u64,
 }
 
 impl<'a> From<&Project> for SurrealAnonymousProject<'a> {
@@ -120,27 +366,84 @@ impl<'a> From<&Project> for SurrealAnonymousProject<'a> {
             reference: value.reference.clone().map(Into::into),
             meta: value.meta.clone().into(),
             highlight: value.highlight,
+            fields: value.fields.clone(),
+            members: value.members.iter().map(Into::into).collect(),
+            share_tokens: value.share_tokens.iter().map(Into::into).collect(),
+            version: value.version,
         }
     }
 }
 
+/// Returns the record the project with the given id is, as child entities must link to it in order to be
+/// counted along with the project.
+fn project_record(id: &str) -> Thing {
+    Thing::from((TABLENAME, record_key(id)))
+}
+
+/// Returns the amount of seconds elapsed since the unix epoch, the same way SurrealDB stores any [`SystemTime`].
+fn secs_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Replaces, in place, every string in the given value that matches any of the keys in the given map by
+/// its corresponding value.
+fn remap_references(value: &mut Value, ids: &HashMap<String, String>) {
+    match value {
+        Value::String(reference) => {
+            if let Some(id) = ids.get(reference.as_str()) {
+                *reference = id.clone();
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| remap_references(item, ids)),
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|field| remap_references(field, ids)),
+        _ => {}
+    }
+}
+
 /// Repository for managing projects persistency
 pub struct SurrealProjectRepository<'a> {
     pub client: &'a Surreal<Client>,
 }
 
+impl<'a> SurrealProjectRepository<'a> {
+    /// Defines the indexes the repository relies on, if not already defined. Must be called before serving any
+    /// request.
+    pub async fn define_indexes(&self) -> Result<()> {
+        self.client
+            .query(QUERY_DEFINE_IDEMPOTENCY_KEY_INDEX)
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing define index query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+}
+
 #[async_trait::async_trait]
 impl<'a> ProjectRepository for SurrealProjectRepository<'a> {
-    async fn find(&self, created_by: &str, id: &str) -> Result<Project> {
+    async fn find(&self, id: &str, uid: &str) -> Result<Project> {
         let resp = self
             .client
             .query(QUERY_FIND_PROJECT)
-            .bind(("created_by", created_by))
+            .bind(("uid", uid))
             .bind(("id", id))
             .await
             .map_err(|err| {
                 error!(
-                    "{} performing select query by created_by and id on surreal: {}",
+                    "{} performing select query by uid and id on surreal: {}",
                     Error::Unknown,
                     err
                 );
@@ -156,15 +459,117 @@ impl<'a> ProjectRepository for SurrealProjectRepository<'a> {
         Ok(item)
     }
 
-    async fn find_all(&self, created_by: &str) -> Result<Vec<ProjectWithCardinalities>> {
+    async fn find_all(
+        &self,
+        uid: &str,
+        filter: &Filter,
+        page: &Page,
+    ) -> Result<Vec<ProjectWithCardinalities>> {
+        let conditions = [
+            (filter.name.is_some(), "AND string::lowercase(name) CONTAINS $name"),
+            (filter.highlight.is_some(), "AND highlight = $highlight"),
+            (
+                filter.created_after.is_some(),
+                "AND meta.created_at.secs_since_epoch >= $created_after",
+            ),
+            (
+                filter.created_before.is_some(),
+                "AND meta.created_at.secs_since_epoch < $created_before",
+            ),
+            (
+                filter.updated_after.is_some(),
+                "AND (meta.updated_at.secs_since_epoch OR meta.created_at.secs_since_epoch) >= $updated_after",
+            ),
+            (
+                filter.updated_before.is_some(),
+                "AND (meta.updated_at.secs_since_epoch OR meta.created_at.secs_since_epoch) < $updated_before",
+            ),
+            (filter.has_reference == Some(true), "AND reference IS NOT NONE"),
+            (filter.has_reference == Some(false), "AND reference IS NONE"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, condition)| enabled.then_some(condition))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        // aliases cannot be referenced from the WHERE clause, hence the sort key expression is required as well
+        let (order, key, operator) = match page.sort_by {
+            SortBy::UpdatedAt => (
+                "last_update DESC",
+                "(meta.updated_at.secs_since_epoch OR meta.created_at.secs_since_epoch)",
+                "<",
+            ),
+            SortBy::CreatedAt => ("created_at DESC", "meta.created_at.secs_since_epoch", "<"),
+            SortBy::Name => ("name ASC", "name", ">"),
+        };
+
+        // resumes right after the cursor by the very same (highlight, key, id) tuple projects are sorted by
+        let after_cursor = page
+            .cursor
+            .is_some()
+            .then(|| {
+                format!(
+                    "AND (
+    (highlight = false AND $cursor_highlight = true)
+    OR (highlight = $cursor_highlight AND (
+        {key} {operator} $cursor_key
+        OR ({key} = $cursor_key AND id > type::thing($tb, $cursor_id))
+    ))
+)"
+                )
+            })
+            .unwrap_or_default();
+
+        let limit = page
+            .limit
+            .is_some()
+            .then_some("LIMIT $limit")
+            .unwrap_or_default();
+
+        let query = format!(
+            "{QUERY_FIND_ALL_PROJECTS_WITH_CARDINALITIES}
+{conditions}
+{after_cursor}
+ORDER BY highlight DESC, {order}, id ASC
+{limit};"
+        );
+
+        let cursor_key = page.cursor.as_ref().map(|cursor| match &cursor.key {
+            SortKey::Secs(secs) => json!(secs),
+            SortKey::Name(name) => json!(name),
+        });
+
         let resp = self
             .client
-            .query(QUERY_FIND_ALL_PROJECTS_WITH_CARDINALITIES)
-            .bind(("created_by", created_by))
+            .query(query)
+            .bind(("uid", uid))
+            .bind(("name", filter.name.as_deref().map(str::to_lowercase)))
+            .bind(("highlight", filter.highlight))
+            .bind(("created_after", filter.created_after.map(secs_since_epoch)))
+            .bind((
+                "created_before",
+                filter.created_before.map(secs_since_epoch),
+            ))
+            .bind(("updated_after", filter.updated_after.map(secs_since_epoch)))
+            .bind((
+                "updated_before",
+                filter.updated_before.map(secs_since_epoch),
+            ))
+            .bind(("limit", page.limit))
+            .bind((
+                "cursor_highlight",
+                page.cursor.as_ref().map(|cursor| cursor.highlight),
+            ))
+            .bind(("cursor_key", cursor_key))
+            .bind(("tb", TABLENAME))
+            .bind((
+                "cursor_id",
+                page.cursor.as_ref().map(|cursor| record_key(&cursor.id)),
+            ))
             .await
             .map_err(|err| {
                 error!(
-                    "{} performing select query by created_by and name on surreal: {}",
+                    "{} performing select query by uid on surreal: {}",
                     Error::Unknown,
                     err
                 );
@@ -178,36 +583,655 @@ impl<'a> ProjectRepository for SurrealProjectRepository<'a> {
         >(resp, 0)?)
     }
 
-    async fn create(&self, project: &mut Project) -> Result<()> {
-        let created: SurrealProject = self
+    async fn find_with_cardinalities(&self, id: &str) -> Result<ProjectWithCardinalities> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_PROJECT_WITH_CARDINALITIES)
+            .bind(("id", id))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by id on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_item::<SurrealProjectWithCardinalities, ProjectWithCardinalities>(resp, 0)
+    }
+
+    async fn find_many(&self, ids: &[String]) -> Result<Vec<Project>> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_PROJECTS)
+            .bind(("ids", ids))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by ids on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_items::<SurrealProject, Project>(resp, 0)
+    }
+
+    async fn find_by_reference(&self, reference: &str) -> Result<Project> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_PROJECT_BY_REFERENCE)
+            .bind(("reference", reference))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by reference on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        let item = surreal::export_item::<SurrealProject, Project>(resp, 0)?;
+        if item.id.is_empty() {
+            return Err(Error::NotFound);
+        }
+
+        Ok(item)
+    }
+
+    async fn find_by_idempotency_key(&self, key: &str, uid: &str) -> Result<Project> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_PROJECT_BY_IDEMPOTENCY_KEY)
+            .bind(("uid", uid))
+            .bind(("key", key))
+            .bind(("now", secs_since_epoch(SystemTime::now())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by idempotency key on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        let item = surreal::export_item::<SurrealProject, Project>(resp, 0)?;
+        if item.id.is_empty() {
+            return Err(Error::NotFound);
+        }
+
+        Ok(item)
+    }
+
+    async fn find_deleted(&self, id: &str, uid: &str) -> Result<Project> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_DELETED_PROJECT)
+            .bind(("uid", uid))
+            .bind(("id", id))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select deleted query by uid and id on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        let item = surreal::export_item::<SurrealProject, Project>(resp, 0)?;
+        if item.id.is_empty() {
+            return Err(Error::NotFound);
+        }
+
+        Ok(item)
+    }
+
+    async fn find_all_deleted(&self, uid: &str) -> Result<Vec<ProjectWithCardinalities>> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_ALL_DELETED_PROJECTS_WITH_CARDINALITIES)
+            .bind(("uid", uid))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select deleted query by uid on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_items::<SurrealProjectWithCardinalities, ProjectWithCardinalities>(resp, 0)
+    }
+
+    async fn create(&self, project: &mut Project, entities: &[Entity]) -> Result<()> {
+        let project_key = Uuid::new_v4().simple().to_string();
+        let project_id = format!("{TABLENAME}:{project_key}");
+
+        let mut query =
+            String::from("BEGIN TRANSACTION;\nCREATE type::thing($tb, $key) CONTENT $content;\n");
+
+        for index in 0..entities.len() {
+            query.push_str(&format!(
+                "CREATE type::table($tb_{index}) CONTENT $content_{index};\n"
+            ));
+        }
+
+        query.push_str("COMMIT TRANSACTION;");
+
+        let mut request = self
+            .client
+            .query(query)
+            .bind(("tb", TABLENAME))
+            .bind(("key", &project_key))
+            .bind(("content", Into::<SurrealAnonymousProject>::into(&*project)));
+
+        for (index, entity) in entities.iter().enumerate() {
+            let content = SurrealEntity {
+                name: entity.name().into(),
+                description: entity.description().into(),
+                project: Some(project_record(&project_id)),
+                meta: Metadata::new(project.meta().created_by().unwrap_or_default()).into(),
+            };
+
+            request = request
+                .bind((format!("tb_{index}"), entity.kind().to_string()))
+                .bind((format!("content_{index}"), content));
+        }
+
+        let mut resp = request.await.map_err(|err| {
+            error!(
+                "{} performing create transaction on surreal: {}",
+                Error::Unknown,
+                err
+            );
+
+            Error::Unknown
+        })?;
+
+        // any failing statement cancels the whole transaction, including the creation of the project itself
+        resp.take::<Vec<Value>>(0).map_err(|err| {
+            error!(
+                "{} creating project along with {} entities: {}",
+                Error::Unknown,
+                entities.len(),
+                err
+            );
+
+            Error::Unknown
+        })?;
+
+        project.id = project_id;
+        Ok(())
+    }
+
+    async fn reserve_idempotency_key(
+        &self,
+        key: &str,
+        uid: &str,
+        expires_at: SystemTime,
+    ) -> Result<()> {
+        let mut resp = self
+            .client
+            .query(QUERY_RESERVE_IDEMPOTENCY_KEY)
+            .bind(("uid", uid))
+            .bind(("key", key))
+            .bind(("now", secs_since_epoch(SystemTime::now())))
+            .bind(("expires_at", secs_since_epoch(expires_at)))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing reserve idempotency key query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        // the unique index on the user and key makes the creation fail if the key is reserved already
+        resp.take::<Vec<Value>>(1).map_err(|err| {
+            warn!(
+                "{} reserving idempotency key {} of user {}: {}",
+                Error::AlreadyExists,
+                key,
+                uid,
+                err
+            );
+
+            Error::AlreadyExists
+        })?;
+
+        Ok(())
+    }
+
+    async fn bind_idempotency_key(&self, key: &str, uid: &str, project: &Project) -> Result<()> {
+        self.client
+            .query(QUERY_BIND_IDEMPOTENCY_KEY)
+            .bind(("tb", TABLENAME))
+            .bind(("uid", uid))
+            .bind(("key", key))
+            .bind(("project", record_key(project.id())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing bind idempotency key query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+
+    async fn release_idempotency_key(&self, key: &str, uid: &str) -> Result<()> {
+        self.client
+            .query(QUERY_RELEASE_IDEMPOTENCY_KEY)
+            .bind(("uid", uid))
+            .bind(("key", key))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing release idempotency key query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+
+    async fn duplicate(&self, source: &Project, project: &mut Project) -> Result<()> {
+        let mut resp = self
+            .client
+            .query(QUERY_FIND_PROJECT_CHILDREN)
+            .bind(("project", project_record(source.id())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select children query by project on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        let project_key = Uuid::new_v4().simple().to_string();
+        let mut ids = HashMap::from([(
+            source.id().to_string(),
+            format!("{TABLENAME}:{project_key}"),
+        )]);
+
+        let mut children = Vec::new();
+        for (index, tablename) in CHILD_TABLENAMES.into_iter().enumerate() {
+            let items = resp.take::<Vec<Value>>(index).map_err(|err| {
+                error!(
+                    "{} taking item from statement {}: {}",
+                    Error::Unknown,
+                    index,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+            for mut item in items {
+                let Some(Value::String(id)) =
+                    item.as_object_mut().and_then(|fields| fields.remove("id"))
+                else {
+                    warn!(
+                        "{} {} child has no id",
+                        Error::MissingFields("id"),
+                        tablename
+                    );
+                    continue;
+                };
+
+                // the link to the duplicate and its metadata are set once created, so children belong to the
+                // owner of the duplicate rather than to the one of the source
+                if let Some(fields) = item.as_object_mut() {
+                    fields.remove("project");
+                    fields.remove("meta");
+                }
+
+                let key = Uuid::new_v4().simple().to_string();
+                ids.insert(id, format!("{tablename}:{key}"));
+                children.push((tablename, key, item));
+            }
+        }
+
+        let mut query =
+            String::from("BEGIN TRANSACTION;\nCREATE type::thing($tb, $key) CONTENT $content;\n");
+
+        for index in 0..children.len() {
+            query.push_str(&format!(
+                "CREATE type::thing($tb_{index}, $key_{index}) CONTENT $content_{index};
+UPDATE type::thing($tb_{index}, $key_{index}) SET project = type::thing($tb, $key), meta = $meta;\n"
+            ));
+        }
+
+        query.push_str("COMMIT TRANSACTION;");
+
+        let mut request = self
+            .client
+            .query(query)
+            .bind(("tb", TABLENAME))
+            .bind(("key", &project_key))
+            .bind(("content", Into::<SurrealAnonymousProject>::into(&*project)))
+            .bind((
+                "meta",
+                Into::<SurrealMetadata>::into(Metadata::new(
+                    project.meta().created_by().unwrap_or_default(),
+                )),
+            ));
+
+        for (index, (tablename, key, mut content)) in children.into_iter().enumerate() {
+            remap_references(&mut content, &ids);
+            request = request
+                .bind((format!("tb_{index}"), tablename))
+                .bind((format!("key_{index}"), key))
+                .bind((format!("content_{index}"), content));
+        }
+
+        request.await.map_err(|err| {
+            error!(
+                "{} performing duplicate transaction on surreal: {}",
+                Error::Unknown,
+                err
+            );
+
+            Error::Unknown
+        })?;
+
+        project.id = format!("{TABLENAME}:{project_key}");
+        Ok(())
+    }
+
+    async fn find_entities(&self, project: &Project) -> Result<Vec<Entity>> {
+        let mut resp = self
+            .client
+            .query(QUERY_FIND_PROJECT_ENTITIES)
+            .bind(("project", project_record(project.id())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select entities query by project on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        let mut entities = Vec::new();
+        for (index, tablename) in CHILD_TABLENAMES.into_iter().enumerate() {
+            let kind = tablename.parse::<EntityKind>().map_err(|err| {
+                error!(
+                    "{} parsing entity kind {}: {}",
+                    Error::Unknown,
+                    tablename,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+            let items = resp.take::<Vec<SurrealEntity>>(index).map_err(|err| {
+                error!(
+                    "{} taking item from statement {}: {}",
+                    Error::Unknown,
+                    index,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+            entities.extend(
+                items
+                    .into_iter()
+                    .map(|item| Entity::new(kind, &item.name, &item.description)),
+            );
+        }
+
+        Ok(entities)
+    }
+
+    async fn update(&self, project: &mut Project) -> Result<()> {
+        let mut content = Into::<SurrealAnonymousProject>::into(&*project);
+        content.version = project.version + 1;
+
+        let resp = self
+            .client
+            .query(QUERY_UPDATE_PROJECT)
+            .bind(("tb", TABLENAME))
+            .bind(("key", record_key(project.id())))
+            .bind(("version", project.version))
+            .bind(("content", content))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing update query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        let updated = surreal::export_items::<SurrealProject, Project>(resp, 0)?;
+        if updated.is_empty() {
+            warn!(
+                "{} project {} is no longer at version {}",
+                Error::Conflict,
+                project.id,
+                project.version
+            );
+
+            return Err(Error::Conflict);
+        }
+
+        project.version += 1;
+        Ok(())
+    }
+
+    async fn update_many(&self, projects: &mut [Project]) -> Result<Vec<Result<()>>> {
+        if projects.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut query = String::from("BEGIN TRANSACTION;\n");
+        for index in 0..projects.len() {
+            query.push_str(&format!(
+                "UPDATE type::thing($tb, $key_{index}) CONTENT $content_{index} WHERE (version OR 0) = $version_{index};\n"
+            ));
+        }
+
+        query.push_str("COMMIT TRANSACTION;");
+
+        let mut request = self.client.query(query).bind(("tb", TABLENAME));
+        for (index, project) in projects.iter().enumerate() {
+            let mut content = Into::<SurrealAnonymousProject>::into(project);
+            content.version = project.version + 1;
+
+            request = request
+                .bind((format!("key_{index}"), record_key(project.id())))
+                .bind((format!("version_{index}"), project.version))
+                .bind((format!("content_{index}"), content));
+        }
+
+        let mut resp = request.await.map_err(|err| {
+            error!(
+                "{} performing update projects transaction on surreal: {}",
+                Error::Unknown,
+                err
+            );
+
+            Error::Unknown
+        })?;
+
+        let mut outcomes = Vec::with_capacity(projects.len());
+        for (index, project) in projects.iter_mut().enumerate() {
+            let items = resp.take::<Vec<Value>>(index).map_err(|err| {
+                error!(
+                    "{} taking item from statement {}: {}",
+                    Error::Unknown,
+                    index,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+            // statements not matching the expected version write nothing, while the others are committed
+            if items.is_empty() {
+                warn!(
+                    "{} project {} is no longer at version {}",
+                    Error::Conflict,
+                    project.id,
+                    project.version
+                );
+
+                outcomes.push(Err(Error::Conflict));
+                continue;
+            }
+
+            project.version += 1;
+            outcomes.push(Ok(()));
+        }
+
+        Ok(outcomes)
+    }
+
+    async fn transfer(&self, project: &mut Project) -> Result<()> {
+        let resp = self
             .client
-            .create(TABLENAME)
-            .content(Into::<SurrealAnonymousProject>::into(&*project))
+            .query(QUERY_TRANSFER_PROJECT)
+            .bind(("id", project_record(project.id())))
+            .bind(("created_by", project.meta().created_by()))
+            .bind(("meta", Into::<SurrealMetadata>::into(project.meta.clone())))
+            .bind((
+                "members",
+                project
+                    .members
+                    .iter()
+                    .map(Into::into)
+                    .collect::<Vec<SurrealMember>>(),
+            ))
+            .bind(("version", project.version + 1))
+            .bind(("expected", project.version))
             .await
             .map_err(|err| {
                 error!(
-                    "{} performing create query on surreal: {}",
+                    "{} performing transfer transaction on surreal: {}",
                     Error::Unknown,
                     err
                 );
+
                 Error::Unknown
             })?;
 
-        project.id = created.id.to_string();
+        let transferred = surreal::export_items::<Value, Value>(resp, 0)?;
+        if transferred.is_empty() {
+            warn!(
+                "{} project {} is no longer at version {}",
+                Error::Conflict,
+                project.id,
+                project.version
+            );
+
+            return Err(Error::Conflict);
+        }
+
+        project.version += 1;
         Ok(())
     }
 
-    async fn update(&self, project: &Project) -> Result<()> {
+    async fn delete(&self, project: &mut Project) -> Result<()> {
+        let resp = self
+            .client
+            .query(QUERY_DELETE_PROJECT)
+            .bind(("tb", TABLENAME))
+            .bind(("key", record_key(project.id())))
+            .bind(("version", project.version))
+            .bind((
+                "content",
+                json!({
+                    "meta": { "deleted_at": project.meta().deleted_at() },
+                    "version": project.version + 1,
+                }),
+            ))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing soft delete query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        let deleted = surreal::export_items::<Value, Value>(resp, 0)?;
+        if deleted.is_empty() {
+            warn!(
+                "{} project {} is no longer at version {}",
+                Error::Conflict,
+                project.id,
+                project.version
+            );
+
+            return Err(Error::Conflict);
+        }
+
+        project.version += 1;
+        Ok(())
+    }
+
+    async fn purge(&self, project: &Project) -> Result<()> {
+        self.client
+            .query(QUERY_PURGE_PROJECT)
+            .bind(("tb", TABLENAME))
+            .bind(("key", record_key(project.id())))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing purge transaction on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        Ok(())
+    }
+
+    async fn purge_deleted_before(&self, deleted_before: SystemTime) -> Result<()> {
         self.client
-            .update((TABLENAME, project.id()))
-            .content(Into::<SurrealProject>::into(project))
+            .query(QUERY_PURGE_DELETED_PROJECTS)
+            .bind(("deleted_before", secs_since_epoch(deleted_before)))
             .await
             .map_err(|err| {
                 error!(
-                    "{} performing create query on surreal: {}",
+                    "{} performing delete query by deleted_at on surreal: {}",
                     Error::Unknown,
                     err
                 );
+
                 Error::Unknown
             })?;
 
@@ -0,0 +1,85 @@
+//! Infrastructure layer for signing and verifying project's share tokens as JWTs.
+
+use super::{application::TokenService, domain::ShareToken};
+use crate::result::{Error, Result};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Determines the claims of a signed share token.
+#[derive(Serialize, Deserialize)]
+struct ShareClaims {
+    jti: String,
+    sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<u64>,
+}
+
+impl From<&ShareToken> for ShareClaims {
+    fn from(value: &ShareToken) -> Self {
+        Self {
+            jti: value.id.clone(),
+            sub: value.project_id.clone(),
+            exp: value
+                .expires_at
+                .and_then(|expires_at| expires_at.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs()),
+        }
+    }
+}
+
+impl From<ShareClaims> for ShareToken {
+    fn from(value: ShareClaims) -> Self {
+        Self {
+            id: value.jti,
+            project_id: value.sub,
+            expires_at: value.exp.map(|exp| UNIX_EPOCH + Duration::from_secs(exp)),
+        }
+    }
+}
+
+/// Token service for signing and verifying share tokens with a symmetric key.
+pub struct JwtTokenService {
+    header: Header,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtTokenService {
+    /// Returns a token service signing tokens through HS256 with the given secret.
+    pub fn from_secret(secret: &[u8]) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_required_spec_claims(&["sub"]);
+
+        Self {
+            header: Header::new(Algorithm::HS256),
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret),
+            validation,
+        }
+    }
+}
+
+impl TokenService for JwtTokenService {
+    fn sign(&self, token: &ShareToken) -> Result<String> {
+        jsonwebtoken::encode(
+            &self.header,
+            &Into::<ShareClaims>::into(token),
+            &self.encoding_key,
+        )
+        .map_err(|err| {
+            error!("{} signing share token: {}", Error::Unknown, err);
+            Error::Unknown
+        })
+    }
+
+    fn verify(&self, token: &str) -> Result<ShareToken> {
+        jsonwebtoken::decode::<ShareClaims>(token, &self.decoding_key, &self.validation)
+            .map(|data| data.claims.into())
+            .map_err(|err| {
+                warn!("{} verifying share token: {}", Error::InvalidToken, err);
+                Error::InvalidToken
+            })
+    }
+}
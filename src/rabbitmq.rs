@@ -2,12 +2,13 @@
 
 use std::sync::Arc;
 
+use crate::correlation::{self, REQUEST_ID_HEADER};
 use crate::result::{Error, Result};
 use futures_util::stream::StreamExt;
 use lapin::{
     options::{BasicAckOptions, BasicConsumeOptions, QueueBindOptions, QueueDeclareOptions},
-    types::FieldTable,
-    Channel,
+    types::{AMQPValue, FieldTable},
+    BasicProperties, Channel,
 };
 use serde::{Deserialize, Serialize};
 
@@ -16,12 +17,15 @@ pub trait EventHandler {
     async fn on_event(&self, body: Vec<u8>) -> Result<()>;
 }
 
-/// Represents all the possible kind of events that may be handled or emited.
+/// Represents all the possible kind of events that may be handled or emited. New variants must always be
+/// appended, since positional formats encode them by index.
 #[derive(strum_macros::Display, Serialize, Deserialize)]
 #[strum(serialize_all = "snake_case")]
 pub(crate) enum EventKind {
     Created,
     Deleted,
+    Transferred,
+    Updated,
 }
 
 pub struct RabbitMqEventBus<'a> {
@@ -121,7 +125,10 @@ impl<'a> RabbitMqEventBus<'a> {
                 continue;
             }
 
-            if let Err(err) = handler.on_event(delivery.data).await {
+            // events triggered while handling this one belong to the same request
+            let request_id = correlation::continue_or_new(request_id(&delivery.properties));
+            if let Err(err) = correlation::scope(request_id, handler.on_event(delivery.data)).await
+            {
                 error!(
                     "{} processing an event from queue {} and tag {}: {}",
                     Error::Unknown,
@@ -135,3 +142,18 @@ impl<'a> RabbitMqEventBus<'a> {
         Ok(())
     }
 }
+
+/// Returns the request id carried by the headers of a delivery with the given properties, if any.
+fn request_id(properties: &BasicProperties) -> Option<&str> {
+    properties
+        .headers()
+        .as_ref()?
+        .inner()
+        .iter()
+        .find(|(key, _)| key.as_str() == REQUEST_ID_HEADER)
+        .and_then(|(_, value)| match value {
+            AMQPValue::LongString(value) => std::str::from_utf8(value.as_bytes()).ok(),
+            AMQPValue::ShortString(value) => Some(value.as_str()),
+            _ => None,
+        })
+}
@@ -6,7 +6,8 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// StdResult is an alias for [`std::result::Result`] where error impl the [`std::error::Error`] trait.
 pub type _StdResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-/// Error represent an error thrown by the application
+/// Error represent an error thrown by the application. Those variants holding a string do it for the name
+/// of the field the error is about.
 #[derive(strum_macros::Display, Debug, PartialEq)]
 pub enum Error {
     #[strum(serialize = "E001")]
@@ -20,7 +21,7 @@ pub enum Error {
     #[strum(serialize = "E005")]
     InvalidToken,
     #[strum(serialize = "E006")]
-    InvalidFormat,
+    InvalidFormat(&'static str),
     #[strum(serialize = "E007")]
     InvalidHeader,
     #[strum(serialize = "E008")]
@@ -30,7 +31,40 @@ pub enum Error {
     #[strum(serialize = "E010")]
     AlreadyExists,
     #[strum(serialize = "E011")]
-    MissingFields,
+    MissingFields(&'static str),
+    #[strum(serialize = "E012")]
+    Conflict,
+    #[strum(serialize = "E013")]
+    RateLimited,
+}
+
+impl Error {
+    /// Returns the name of the field the error is about, if any.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            Error::InvalidFormat(field) | Error::MissingFields(field) => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Returns a short description of the error in screaming snake case, as a stable reason for clients.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Error::Unknown => "UNKNOWN",
+            Error::NotFound => "NOT_FOUND",
+            Error::NotAvailable => "NOT_AVAILABLE",
+            Error::Unauthorized => "UNAUTHORIZED",
+            Error::InvalidToken => "INVALID_TOKEN",
+            Error::InvalidFormat(_) => "INVALID_FORMAT",
+            Error::InvalidHeader => "INVALID_HEADER",
+            Error::WrongCredentials => "WRONG_CREDENTIALS",
+            Error::RegexNotMatch => "REGEX_NOT_MATCH",
+            Error::AlreadyExists => "ALREADY_EXISTS",
+            Error::MissingFields(_) => "MISSING_FIELDS",
+            Error::Conflict => "CONFLICT",
+            Error::RateLimited => "RATE_LIMITED",
+        }
+    }
 }
 
 impl From<Error> for String {
@@ -49,3 +49,8 @@ pub fn export_items<T: DeserializeOwned, U: From<T>>(
 
     Ok(items.into_iter().map(Into::into).collect::<Vec<U>>())
 }
+
+/// Returns the key of the record with the given id, that is, the id without its table name.
+pub fn record_key(id: &str) -> &str {
+    id.split_once(':').map(|(_, key)| key).unwrap_or(id)
+}
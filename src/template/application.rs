@@ -0,0 +1,76 @@
+//! Application layer of the template entity.
+
+use super::domain::{self, Template};
+use crate::{
+    metadata::domain::Metadata,
+    project::application::ProjectRepository,
+    result::{Error, Result},
+};
+use std::sync::Arc;
+
+#[async_trait::async_trait]
+pub trait TemplateRepository {
+    async fn find(&self, id: &str, created_by: &str) -> Result<Template>;
+    async fn find_all(&self, created_by: &str) -> Result<Vec<Template>>;
+    async fn create(&self, template: &mut Template) -> Result<()>;
+}
+
+pub struct TemplateApplication<T: TemplateRepository, P: ProjectRepository> {
+    pub template_repo: Arc<T>,
+    pub project_repo: Arc<P>,
+}
+
+impl<T: TemplateRepository, P: ProjectRepository> TemplateApplication<T, P> {
+    /// Returns the built-in template with the given id, if any, or the one saved by the given user otherwise.
+    pub async fn get(&self, id: &str, uid: &str) -> Result<Template> {
+        info!("processing a \"get\" template request for user {uid}");
+
+        if let Some(template) = domain::builtins()
+            .into_iter()
+            .find(|template| template.id == id)
+        {
+            return Ok(template);
+        }
+
+        self.template_repo.find(id, uid).await
+    }
+
+    /// Returns all the built-in templates followed by those saved by the given user.
+    pub async fn list(&self, uid: &str) -> Result<Vec<Template>> {
+        info!("processing a \"list\" templates request for user {uid}");
+
+        let mut templates = domain::builtins();
+        templates.extend(self.template_repo.find_all(uid).await?);
+        Ok(templates)
+    }
+
+    /// Saves the current state of the project with the given id as a new template owned by the given user.
+    pub async fn save(
+        &self,
+        project_id: &str,
+        name: &str,
+        description: &str,
+        uid: &str,
+    ) -> Result<Template> {
+        info!("processing a \"save\" template request for user {uid}");
+
+        if name.is_empty() {
+            return Err(Error::MissingFields("name"));
+        }
+
+        let project = self.project_repo.find(project_id, uid).await?;
+        let entities = self.project_repo.find_entities(&project).await?;
+
+        let mut template = Template {
+            id: "".to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            fields: project.fields().clone(),
+            entities,
+            meta: Metadata::new(uid),
+        };
+
+        self.template_repo.create(&mut template).await?;
+        Ok(template)
+    }
+}
@@ -0,0 +1,223 @@
+//! Domain layer of the template entity.
+
+use crate::metadata::domain::Metadata;
+use std::collections::BTreeMap;
+
+/// Represents all the kinds of entity a project may contain
+#[derive(strum_macros::Display, strum_macros::EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "snake_case")]
+pub enum EntityKind {
+    Character,
+    Object,
+    Location,
+    Event,
+}
+
+/// Represents a placeholder entity to be created along with any project bootstrapped from a template
+#[derive(Debug, Clone)]
+pub struct Entity {
+    pub(super) kind: EntityKind,
+    pub(super) name: String,
+    pub(super) description: String,
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, name: &str, description: &str) -> Self {
+        Entity {
+            kind,
+            name: name.to_string(),
+            description: description.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> EntityKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// Represents a template
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub(super) id: String,
+    pub(super) name: String,
+    pub(super) description: String,
+    pub(super) fields: BTreeMap<String, String>,
+    pub(super) entities: Vec<Entity>,
+    pub(super) meta: Metadata,
+}
+
+impl Template {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, String> {
+        &self.fields
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn meta(&self) -> &Metadata {
+        &self.meta
+    }
+
+    /// Returns true if, and only if, the template is shipped along with the application instead of being
+    /// saved by any user.
+    pub fn is_builtin(&self) -> bool {
+        self.meta.created_by().is_none()
+    }
+}
+
+/// Returns the template with the given name, entities and fields, without any owner.
+fn builtin(
+    id: &str,
+    name: &str,
+    description: &str,
+    entities: &[(EntityKind, &str, &str)],
+    fields: &[(&str, &str)],
+) -> Template {
+    Template {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        fields: fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        entities: entities
+            .iter()
+            .map(|(kind, name, description)| Entity::new(*kind, name, description))
+            .collect(),
+        meta: Metadata::default(),
+    }
+}
+
+/// Returns all the templates shipped along with the application.
+pub fn builtins() -> Vec<Template> {
+    vec![
+        builtin(
+            "fantasy-kingdom",
+            "Fantasy kingdom",
+            "A realm of kings, magic and ancient rivalries.",
+            &[
+                (
+                    EntityKind::Character,
+                    "The monarch",
+                    "Ruler of the kingdom.",
+                ),
+                (
+                    EntityKind::Character,
+                    "The heir",
+                    "Next in line to the throne.",
+                ),
+                (
+                    EntityKind::Location,
+                    "The capital",
+                    "Seat of the royal court.",
+                ),
+                (
+                    EntityKind::Location,
+                    "The borderlands",
+                    "Where the kingdom's rule fades.",
+                ),
+                (
+                    EntityKind::Object,
+                    "The crown",
+                    "Symbol of the monarch's power.",
+                ),
+                (
+                    EntityKind::Event,
+                    "The coronation",
+                    "The day the monarch took the throne.",
+                ),
+            ],
+            &[("Magic system", ""), ("Ruling house", ""), ("Religion", "")],
+        ),
+        builtin(
+            "murder-mystery",
+            "Murder mystery",
+            "A crime, a handful of suspects and a single truth.",
+            &[
+                (
+                    EntityKind::Character,
+                    "The victim",
+                    "Whose death starts it all.",
+                ),
+                (
+                    EntityKind::Character,
+                    "The detective",
+                    "Who seeks the truth.",
+                ),
+                (EntityKind::Character, "The culprit", "Who hides the truth."),
+                (
+                    EntityKind::Location,
+                    "The crime scene",
+                    "Where the body was found.",
+                ),
+                (
+                    EntityKind::Object,
+                    "The murder weapon",
+                    "What took the victim's life.",
+                ),
+                (EntityKind::Event, "The murder", "The crime to be solved."),
+            ],
+            &[("Motive", ""), ("Time of death", "")],
+        ),
+        builtin(
+            "sci-fi-colony",
+            "Sci-fi colony",
+            "A settlement struggling to survive on a distant world.",
+            &[
+                (
+                    EntityKind::Character,
+                    "The governor",
+                    "Leader of the colony.",
+                ),
+                (
+                    EntityKind::Character,
+                    "The chief engineer",
+                    "Who keeps the colony running.",
+                ),
+                (
+                    EntityKind::Location,
+                    "The settlement",
+                    "Home of the colonists.",
+                ),
+                (
+                    EntityKind::Location,
+                    "The landing site",
+                    "Where the colonists arrived.",
+                ),
+                (
+                    EntityKind::Object,
+                    "The colony ship",
+                    "What brought the colonists here.",
+                ),
+                (
+                    EntityKind::Event,
+                    "The landfall",
+                    "The arrival to the new world.",
+                ),
+            ],
+            &[("Planet", ""), ("Technology level", ""), ("Year", "")],
+        ),
+    ]
+}
@@ -0,0 +1,176 @@
+//! Infrastructure layer for managing templates persistency on SurrealDB.
+
+use super::{
+    application::TemplateRepository,
+    domain::{Entity, EntityKind, Template},
+};
+use crate::metadata::repository::SurrealMetadata;
+use crate::result::{Error, Result};
+use crate::surreal;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+
+const TABLENAME: &str = "template";
+
+const QUERY_FIND_TEMPLATE: &str =
+    "SELECT * FROM template WHERE id = $id AND meta.created_by = $created_by;";
+
+const QUERY_FIND_ALL_TEMPLATES: &str =
+    "SELECT * FROM template WHERE meta.created_by = $created_by ORDER BY name;";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SurrealEntity<'a> {
+    kind: Cow<'a, str>,
+    name: Cow<'a, str>,
+    description: Cow<'a, str>,
+}
+
+impl<'a> From<&Entity> for SurrealEntity<'a> {
+    fn from(value: &Entity) -> Self {
+        SurrealEntity {
+            kind: value.kind.to_string().into(),
+            name: value.name.clone().into(),
+            description: value.description.clone().into(),
+        }
+    }
+}
+
+impl<'a> TryFrom<SurrealEntity<'a>> for Entity {
+    type Error = Error;
+
+    fn try_from(value: SurrealEntity<'a>) -> Result<Self> {
+        let kind = value.kind.parse::<EntityKind>().map_err(|err| {
+            warn!(
+                "{} parsing entity kind {}: {}",
+                Error::InvalidFormat("kind"),
+                value.kind,
+                err
+            );
+            Error::InvalidFormat("kind")
+        })?;
+
+        Ok(Entity {
+            kind,
+            name: value.name.into(),
+            description: value.description.into(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SurrealTemplate<'a> {
+    id: Thing,
+    name: Cow<'a, str>,
+    description: Cow<'a, str>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+    #[serde(default)]
+    entities: Vec<SurrealEntity<'a>>,
+    meta: SurrealMetadata<'a>,
+}
+
+impl<'a> From<SurrealTemplate<'a>> for Template {
+    fn from(value: SurrealTemplate<'a>) -> Self {
+        Template {
+            id: value.id.to_string(),
+            name: value.name.into(),
+            description: value.description.into(),
+            fields: value.fields,
+            entities: value
+                .entities
+                .into_iter()
+                .filter_map(|entity| entity.try_into().ok())
+                .collect(),
+            meta: value.meta.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SurrealAnonymousTemplate<'a> {
+    name: Cow<'a, str>,
+    description: Cow<'a, str>,
+    fields: BTreeMap<String, String>,
+    entities: Vec<SurrealEntity<'a>>,
+    meta: SurrealMetadata<'a>,
+}
+
+impl<'a> From<&Template> for SurrealAnonymousTemplate<'a> {
+    fn from(value: &Template) -> Self {
+        SurrealAnonymousTemplate {
+            name: value.name.clone().into(),
+            description: value.description.clone().into(),
+            fields: value.fields.clone(),
+            entities: value.entities.iter().map(Into::into).collect(),
+            meta: value.meta.clone().into(),
+        }
+    }
+}
+
+/// Repository for managing templates persistency
+pub struct SurrealTemplateRepository<'a> {
+    pub client: &'a Surreal<Client>,
+}
+
+#[async_trait::async_trait]
+impl<'a> TemplateRepository for SurrealTemplateRepository<'a> {
+    async fn find(&self, id: &str, created_by: &str) -> Result<Template> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_TEMPLATE)
+            .bind(("created_by", created_by))
+            .bind(("id", id))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by created_by and id on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_item::<SurrealTemplate, Template>(resp, 0)
+    }
+
+    async fn find_all(&self, created_by: &str) -> Result<Vec<Template>> {
+        let resp = self
+            .client
+            .query(QUERY_FIND_ALL_TEMPLATES)
+            .bind(("created_by", created_by))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing select query by created_by on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+
+                Error::Unknown
+            })?;
+
+        surreal::export_items::<SurrealTemplate, Template>(resp, 0)
+    }
+
+    async fn create(&self, template: &mut Template) -> Result<()> {
+        let created: SurrealTemplate = self
+            .client
+            .create(TABLENAME)
+            .content(Into::<SurrealAnonymousTemplate>::into(&*template))
+            .await
+            .map_err(|err| {
+                error!(
+                    "{} performing create query on surreal: {}",
+                    Error::Unknown,
+                    err
+                );
+                Error::Unknown
+            })?;
+
+        template.id = created.id.to_string();
+        Ok(())
+    }
+}